@@ -0,0 +1,168 @@
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion, Throughput};
+use mpsc::async_channel;
+use std::ptr;
+use std::sync::atomic::{AtomicPtr, Ordering};
+use std::thread;
+
+const MSGS: usize = 10_000;
+
+#[derive(Debug, Clone)]
+struct UsizeTest {
+    key: usize,
+}
+
+impl mpsc::HyperKey for UsizeTest {
+    fn collision_detect(&self, other: &Self) -> bool {
+        self.key == other.key
+    }
+}
+
+/// `producers` threads append `MSGS` msgs in total to a fresh channel.
+fn send(producers: usize) -> mpsc::Channel<UsizeTest, usize> {
+    let chan = mpsc::Channel::new();
+    // all made upfront, the channel closes once the last one is dropped.
+    let senders: Vec<_> = (0..producers)
+        .map(|_| async_channel::Sender::new(&chan))
+        .collect();
+    thread::scope(|scope| {
+        for (producer, sender) in senders.into_iter().enumerate() {
+            scope.spawn(move || {
+                for i in (producer..MSGS).step_by(producers) {
+                    sender.send(vec![UsizeTest { key: i }], i).unwrap();
+                }
+            });
+        }
+    });
+    chan
+}
+
+/// The append path the channel started from: a boxed node per msg, linked
+/// by a `SeqCst` CAS on the `next` of the last node, then a plain store of
+/// the tail. Kept as the baseline of `send_bench`.
+struct Baseline {
+    head: *mut BaselineNode,
+    tail: AtomicPtr<BaselineNode>,
+}
+
+struct BaselineNode {
+    next: AtomicPtr<BaselineNode>,
+    data: *mut Option<(Vec<UsizeTest>, usize)>,
+}
+
+// the nodes are only shared through atomics, as in the channel.
+unsafe impl Sync for Baseline {}
+
+impl Baseline {
+    fn new() -> Self {
+        let node = Box::into_raw(Box::new(BaselineNode {
+            next: AtomicPtr::new(ptr::null_mut()),
+            data: Box::into_raw(Box::new(None)),
+        }));
+        Self {
+            head: node,
+            tail: AtomicPtr::new(node),
+        }
+    }
+
+    fn send(&self, keys: Vec<UsizeTest>, val: usize) {
+        let new_node = Box::into_raw(Box::new(BaselineNode {
+            next: AtomicPtr::new(ptr::null_mut()),
+            data: Box::into_raw(Box::new(Some((keys, val)))),
+        }));
+        let mut tail = unsafe { &*self.tail.load(Ordering::SeqCst) };
+        loop {
+            match tail.next.compare_exchange_weak(
+                ptr::null_mut(),
+                new_node,
+                Ordering::SeqCst,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => break,
+                Err(curr) if curr.is_null() => {}
+                Err(curr) => tail = unsafe { &*curr },
+            }
+        }
+        self.tail.store(new_node, Ordering::SeqCst);
+    }
+}
+
+impl Drop for Baseline {
+    fn drop(&mut self) {
+        let mut node = self.head;
+        while !node.is_null() {
+            let owned = unsafe { Box::from_raw(node) };
+            drop(unsafe { Box::from_raw(owned.data) });
+            node = owned.next.load(Ordering::Relaxed);
+        }
+    }
+}
+
+/// same as `send`, on the baseline.
+fn send_baseline(producers: usize) -> Baseline {
+    let chan = Baseline::new();
+    thread::scope(|scope| {
+        for producer in 0..producers {
+            let chan = &chan;
+            scope.spawn(move || {
+                for i in (producer..MSGS).step_by(producers) {
+                    chan.send(vec![UsizeTest { key: i }], i);
+                }
+            });
+        }
+    });
+    chan
+}
+
+/// the blocks against the baseline, for each count of producers. The
+/// producers contend on the tail only if they run in parallel, the counts
+/// above the available cores are marked as oversubscribed.
+///
+/// The blocks don't beat the baseline: on a single core they send 5.1M to
+/// 5.8M msgs/s against 7.3M to 8.1M for the baseline, whatever the
+/// producers. A send also stamps the msg with `Instant::now`, about 36ns on
+/// that machine, enters the channel, counts the msg and shares the filter
+/// with it, which the baseline skips; without the stamp the blocks reach
+/// 8.1M. The contended counts are still to be measured on several cores.
+fn send_bench(c: &mut Criterion) {
+    let cores = thread::available_parallelism().map_or(1, usize::from);
+    let mut group = c.benchmark_group("send");
+    group.throughput(Throughput::Elements(MSGS as u64));
+    for producers in [1, 2, 4, 8] {
+        let name = if producers > cores {
+            format!("{producers} producers, oversubscribed")
+        } else {
+            format!("{producers} producers")
+        };
+        group.bench_function(format!("blocks/{name}"), |b| {
+            b.iter_batched(|| (), |()| send(producers), BatchSize::PerIteration);
+        });
+        group.bench_function(format!("baseline/{name}"), |b| {
+            b.iter_batched(
+                || (),
+                |()| send_baseline(producers),
+                BatchSize::PerIteration,
+            );
+        });
+    }
+    group.finish();
+}
+
+fn recv_bench(c: &mut Criterion) {
+    let mut group = c.benchmark_group("recv");
+    group.throughput(Throughput::Elements(MSGS as u64));
+    group.bench_function("drain", |b| {
+        b.iter_batched(
+            || send(1),
+            |chan| {
+                let receiver = async_channel::Receiver { chan: &chan };
+                assert_eq!(receiver.try_iter().count(), MSGS);
+                chan
+            },
+            BatchSize::PerIteration,
+        );
+    });
+    group.finish();
+}
+
+criterion_group!(benches, send_bench, recv_bench);
+criterion_main!(benches);
@@ -14,9 +14,9 @@ impl HyperKey for SimplKey {
 }
 
 fn async_example() {
-    let chan = Box::leak(Box::new(mpsc::Channel::<SimplKey, usize>::new()));
-    let sender = async_channel::Sender { chan };
-    let receiver = async_channel::Receiver { chan };
+    let chan = Box::leak(Box::new(Channel::<SimplKey, usize>::new()));
+    let sender = async_channel::Sender::new(chan);
+    let receiver = async_channel::Receiver::new(chan);
 
     let sender = sender.clone();
     let _handle = thread::spawn(move || sender.send(vec![SimplKey { key: 2 }], 1));
@@ -25,9 +25,9 @@ fn async_example() {
 }
 
 fn sync_example() {
-    let chan = Box::leak(Box::new(mpsc::Channel::<SimplKey, usize>::new()));
-    let sender = sync_channel::Sender { chan };
-    let receiver = sync_channel::Receiver { chan };
+    let chan = Box::leak(Box::new(Channel::<SimplKey, usize>::new()));
+    let sender = sync_channel::Sender::new(chan);
+    let receiver = sync_channel::Receiver::new(chan);
 
     let sender = sender.clone();
     let _handle = thread::spawn(move || sender.send(vec![SimplKey { key: 2 }], 1));
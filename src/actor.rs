@@ -0,0 +1,274 @@
+use crate::{Channel, HyperKey, Msg};
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::hash::Hash;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc, Condvar, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+/// Handles the msgs of a keyed actor.
+///
+/// Msgs sharing a key are handled one after another, msgs with different
+/// keys are handled in parallel by the workers.
+///
+/// The channel serializes the msgs by `HyperKey::collision_detect`, while the
+/// states are looked up by `Eq` and `Hash`. Two keys must collide exactly
+/// when they are equal: colliding keys which aren't equal get a state each,
+/// handled in turn, and equal keys which don't collide share a state, which
+/// `handle` then waits on instead of running in parallel.
+pub trait Handler<K, V>: Send + Sync + 'static {
+    type State: Send + 'static;
+
+    /// creates the state of a key the first time a msg arrives for it,
+    /// or after its previous state was evicted.
+    fn init(&self, key: &K) -> Self::State;
+
+    fn handle(&self, state: &mut Self::State, val: V);
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Config {
+    /// number of threads running the handler.
+    pub workers: usize,
+    /// drops the state of a key once it has been idle for this long.
+    pub idle_timeout: Option<Duration>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            workers: 4,
+            idle_timeout: None,
+        }
+    }
+}
+
+struct Slot<S> {
+    state: Arc<Mutex<S>>,
+    last_used: Instant,
+}
+
+type States<K, S> = Arc<Mutex<HashMap<K, Slot<S>>>>;
+
+/// wakes the dispatcher up when a msg may have become deliverable.
+#[derive(Default)]
+struct Wakeup {
+    dirty: Mutex<bool>,
+    cond: Condvar,
+}
+
+impl Wakeup {
+    fn notify(&self) {
+        *self.dirty.lock().unwrap() = true;
+        self.cond.notify_one();
+    }
+
+    /// forgets the past notifications, called before looking for work.
+    fn clear(&self) {
+        *self.dirty.lock().unwrap() = false;
+    }
+
+    /// blocks until notified since the last `clear`, or until the timeout.
+    fn wait(&self, timeout: Option<Duration>) {
+        let dirty = self.dirty.lock().unwrap();
+        let _dirty = match timeout {
+            Some(timeout) => {
+                self.cond
+                    .wait_timeout_while(dirty, timeout, |dirty| !*dirty)
+                    .unwrap()
+                    .0
+            }
+            None => self.cond.wait_while(dirty, |dirty| !*dirty).unwrap(),
+        };
+    }
+}
+
+/// A keyed actor built on top of the `Channel`.
+///
+/// The `Channel` holds the key of a msg until the handler returns, which is
+/// what keeps the msgs of the same key serial. `K` must collide exactly when
+/// it's equal, see `Handler`.
+pub struct Actor<K, V, H>
+where
+    K: HyperKey + Clone,
+    H: Handler<K, V>,
+{
+    chan: Arc<Channel<K, V>>,
+    states: States<K, H::State>,
+    stopped: Arc<AtomicBool>,
+    wakeup: Arc<Wakeup>,
+    dispatcher: Option<JoinHandle<()>>,
+    workers: Vec<JoinHandle<()>>,
+}
+
+impl<K, V, H> Actor<K, V, H>
+where
+    K: HyperKey + Send + Sync + Debug + Clone + Eq + Hash + 'static,
+    V: Send + Debug + 'static,
+    H: Handler<K, V>,
+{
+    /// spawns the dispatcher and the workers.
+    ///
+    /// # Panics
+    /// will panic if a worker panicked while holding the states.
+    #[must_use]
+    pub fn spawn(handler: H, config: Config) -> Self {
+        let chan = Arc::new(Channel::new());
+        let states: States<K, H::State> = Arc::default();
+        let stopped = Arc::new(AtomicBool::new(false));
+        let in_flight = Arc::new(AtomicUsize::new(0));
+        let wakeup = Arc::new(Wakeup::default());
+        let handler = Arc::new(handler);
+
+        let (tx, rx) = mpsc::channel::<Msg<K, V>>();
+        let rx = Arc::new(Mutex::new(rx));
+
+        let workers = (0..config.workers.max(1))
+            .map(|_| {
+                let rx = rx.clone();
+                let states = states.clone();
+                let handler = handler.clone();
+                let in_flight = in_flight.clone();
+                let wakeup = wakeup.clone();
+                thread::spawn(move || loop {
+                    // the dispatcher is gone.
+                    let Ok(msg) = rx.lock().unwrap().recv() else {
+                        return;
+                    };
+                    handle(&*handler, &states, msg);
+                    in_flight.fetch_sub(1, Ordering::SeqCst);
+                    wakeup.notify();
+                })
+            })
+            .collect();
+
+        let dispatcher = {
+            let chan = chan.clone();
+            let states = states.clone();
+            let stopped = stopped.clone();
+            let wakeup = wakeup.clone();
+            thread::spawn(move || loop {
+                wakeup.clear();
+                // loaded before the scan, a worker finishing during the scan may
+                // release a key the scan has already skipped.
+                let drained =
+                    stopped.load(Ordering::SeqCst) && in_flight.load(Ordering::SeqCst) == 0;
+                if let Ok(msg) = chan.try_recv() {
+                    in_flight.fetch_add(1, Ordering::SeqCst);
+                    // every worker is gone, the msg is dropped and its key released.
+                    if tx.send(msg).is_err() {
+                        in_flight.fetch_sub(1, Ordering::SeqCst);
+                    }
+                    continue;
+                }
+                // nothing is deliverable, either the channel is empty or every
+                // pending msg waits for a key held by a worker.
+                if drained {
+                    return;
+                }
+                if let Some(timeout) = config.idle_timeout {
+                    evict(&states, timeout);
+                }
+                // woken up by a send, a handled msg or the drop.
+                wakeup.wait(config.idle_timeout);
+            })
+        };
+
+        Self {
+            chan,
+            states,
+            stopped,
+            wakeup,
+            dispatcher: Some(dispatcher),
+            workers,
+        }
+    }
+
+    pub fn send(&self, key: K, val: V) {
+        // unbounded, never fails.
+        let _ = self.chan.send(vec![key], val);
+        self.wakeup.notify();
+    }
+
+    /// number of keys which currently own a state.
+    ///
+    /// # Panics
+    /// will panic if a worker panicked while holding the states.
+    #[must_use]
+    pub fn states_len(&self) -> usize {
+        self.states.lock().unwrap().len()
+    }
+}
+
+impl<K, V, H> Drop for Actor<K, V, H>
+where
+    K: HyperKey + Clone,
+    H: Handler<K, V>,
+{
+    /// waits until every sent msg is handled.
+    fn drop(&mut self) {
+        self.stopped.store(true, Ordering::SeqCst);
+        self.wakeup.notify();
+        if let Some(dispatcher) = self.dispatcher.take() {
+            let _ = dispatcher.join();
+        }
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        }
+    }
+}
+
+fn handle<K, V, H>(handler: &H, states: &States<K, H::State>, msg: Msg<K, V>)
+where
+    K: HyperKey + Clone + Eq + Hash,
+    H: Handler<K, V>,
+{
+    let (guard, val) = msg.into_parts();
+    let key = &guard.keys[0];
+
+    // kept until `last_used` is refreshed, `evict` never drops a state in use.
+    let mut state = None;
+    // the handler never runs under the states lock, a panic would poison it.
+    let outcome = panic::catch_unwind(AssertUnwindSafe(|| {
+        let found = states.lock().unwrap().get_mut(key).map(|slot| {
+            slot.last_used = Instant::now();
+            slot.state.clone()
+        });
+        // the key is held, nobody else inits it meanwhile.
+        let state = state.insert(found.unwrap_or_else(|| {
+            let state = Arc::new(Mutex::new(handler.init(key)));
+            let slot = Slot {
+                state: state.clone(),
+                last_used: Instant::now(),
+            };
+            states.lock().unwrap().insert(key.clone(), slot);
+            state
+        }));
+        handler.handle(&mut state.lock().unwrap(), val);
+    }));
+
+    let mut states = states.lock().unwrap();
+    if outcome.is_err() {
+        // the state may be half updated, the next msg starts over.
+        states.remove(key);
+    } else if let Some(slot) = states.get_mut(key) {
+        slot.last_used = Instant::now();
+    }
+    drop(states);
+    drop(state);
+    // the key is released after the state is put back.
+    drop(guard);
+}
+
+fn evict<K, S>(states: &States<K, S>, timeout: Duration)
+where
+    K: Eq + Hash,
+{
+    // a state in use is shared with the worker, so it's never evicted.
+    states
+        .lock()
+        .unwrap()
+        .retain(|_, slot| Arc::strong_count(&slot.state) > 1 || slot.last_used.elapsed() < timeout);
+}
@@ -1,12 +1,15 @@
-use crate::{Channel, HyperKey, Msg, RecvError};
+use crate::{Channel, HyperKey, Iter, Msg, MsgGroup, RecvError, SendError, TryIter};
 use std::fmt::Debug;
+use std::time::{Duration, Instant};
 
+/// A sender borrowing the channel, the channel is closed when the last one
+/// is dropped.
 pub struct Sender<'a, K, V>
 where
     K: HyperKey + Send + Clone,
     V: Send,
 {
-    pub chan: &'a Channel<K, V>,
+    chan: &'a Channel<K, V>,
 }
 
 pub struct Receiver<'a, K, V>
@@ -22,11 +25,51 @@ where
     K: HyperKey + Send + Debug + Clone,
     V: Send + Debug,
 {
+    /// Sends a msg without waiting for its delivery, waits for room on a
+    /// bounded channel.
+    ///
+    /// # Errors
+    /// will return `SendError` if the channel is closed, or closed while
+    /// waiting for room.
+    pub fn send(&self, keys: Vec<K>, val: V) -> Result<(), SendError> {
+        self.chan.send(keys, val)
+    }
+
+    /// Sends the msgs as one contiguous segment, the receiver sees either all
+    /// of them or none, and no other sender's msg is interleaved.
+    ///
+    /// On a folding channel these msgs are never folded into pending ones.
+    ///
+    /// # Errors
+    /// will return `SendError` if the channel is closed.
+    pub fn send_all(&self, msgs: impl IntoIterator<Item = (Vec<K>, V)>) -> Result<(), SendError> {
+        self.chan.append_all(msgs).map(|_| ())
+    }
+
+    /// Sends a msg which is delivered before lower priority msgs, unless an
+    /// earlier msg with colliding keys is still pending.
+    ///
     /// # Errors
-    /// will return `SendError` if data structure panics in the Channel.
+    /// will return `SendError` if the channel is closed.
+    pub fn send_with_priority(&self, keys: Vec<K>, val: V, priority: u8) -> Result<(), SendError> {
+        self.chan.send_with_priority(keys, val, priority)
+    }
+
+    /// Sends a msg which is invisible to the receiver until `at`. It is kept
+    /// aside meanwhile, and queued behind the msgs sent before it matures.
     ///
-    pub fn send(&self, keys: Vec<K>, val: V) {
-        self.chan.send(keys, val);
+    /// # Errors
+    /// will return `SendError` if the channel is closed.
+    pub fn send_at(&self, keys: Vec<K>, val: V, at: Instant) -> Result<(), SendError> {
+        self.chan.send_at(keys, val, at)
+    }
+
+    /// Sends a msg which is invisible to the receiver for `delay`.
+    ///
+    /// # Errors
+    /// will return `SendError` if the channel is closed.
+    pub fn send_after(&self, keys: Vec<K>, val: V, delay: Duration) -> Result<(), SendError> {
+        self.send_at(keys, val, Instant::now() + delay)
     }
 }
 
@@ -35,11 +78,111 @@ where
     K: HyperKey + Send + Debug + Clone,
     V: Send + Debug,
 {
+    /// Waits until the first msg is sent, or until a scheduled msg matures
+    /// if nothing else is pending, then receives the first deliverable one.
+    ///
     /// # Errors
-    /// will return `RecvError` if inner data structure panics.
+    /// will return `RecvError` if every visible msg collides with the active
+    /// keys, or if the channel is drained.
     pub fn recv(&self) -> Result<Msg<K, V>, RecvError> {
         self.chan.recv()
     }
+
+    /// Receives up to `max` msgs which collide neither with the active keys
+    /// nor with each other, their keys are registered at once.
+    ///
+    /// # Errors
+    /// will return `RecvError` if no msg is deliverable.
+    pub fn recv_batch(&self, max: usize) -> Result<Vec<Msg<K, V>>, RecvError> {
+        self.chan.recv_batch(max)
+    }
+
+    /// Same as `recv_batch`, but once the first msgs are received, waits up
+    /// to `linger` for more msgs to become deliverable, trading latency for
+    /// larger batches.
+    ///
+    /// # Errors
+    /// will return `RecvError` if no msg is deliverable.
+    pub fn recv_batch_timeout(
+        &self,
+        max: usize,
+        linger: Duration,
+    ) -> Result<Vec<Msg<K, V>>, RecvError> {
+        self.chan.recv_batch_timeout(max, linger)
+    }
+
+    /// Receives the first deliverable msg together with every pending msg
+    /// with the same keys, the keys are acquired once for the whole group.
+    ///
+    /// # Errors
+    /// will return `RecvError` if no msg is deliverable.
+    pub fn recv_group(&self) -> Result<MsgGroup<K, V>, RecvError> {
+        self.chan.recv_group()
+    }
+
+    /// Blocks for every msg until every sender is dropped, or the channel is
+    /// closed, and the channel is drained.
+    #[must_use]
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        Iter { chan: self.chan }
+    }
+
+    /// Drains the msgs which are deliverable right now.
+    #[must_use]
+    pub fn try_iter(&self) -> TryIter<'_, K, V> {
+        TryIter { chan: self.chan }
+    }
+}
+
+impl<'a, K, V> IntoIterator for &'a Receiver<'_, K, V>
+where
+    K: HyperKey + Send + Debug + Clone,
+    V: Send + Debug,
+{
+    type Item = Msg<K, V>;
+    type IntoIter = Iter<'a, K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, K, V> IntoIterator for Receiver<'a, K, V>
+where
+    K: HyperKey + Send + Debug + Clone,
+    V: Send + Debug,
+{
+    type Item = Msg<K, V>;
+    type IntoIter = Iter<'a, K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        Iter { chan: self.chan }
+    }
+}
+
+impl<'a, K, V> Receiver<'a, K, V>
+where
+    K: HyperKey + Send + Clone,
+    V: Send,
+{
+    #[must_use]
+    pub fn new(chan: &'a Channel<K, V>) -> Self {
+        Self { chan }
+    }
+}
+
+impl<'a, K, V> Sender<'a, K, V>
+where
+    K: HyperKey + Send + Debug + Clone,
+    V: Send + Debug,
+{
+    /// A sender made once the channel is closed, e.g. after the last sender
+    /// was dropped, can't send anymore.
+    #[must_use]
+    pub fn new(chan: &'a Channel<K, V>) -> Self {
+        chan.add_sender();
+        Self { chan }
+    }
 }
 
 impl<K, V> Clone for Sender<'_, K, V>
@@ -48,8 +191,16 @@ where
     V: Send + Debug,
 {
     fn clone(&self) -> Self {
-        Self {
-            chan: <&Channel<K, V>>::clone(&self.chan),
-        }
+        Self::new(self.chan)
+    }
+}
+
+impl<K, V> Drop for Sender<'_, K, V>
+where
+    K: HyperKey + Send + Clone,
+    V: Send,
+{
+    fn drop(&mut self) {
+        self.chan.drop_sender();
     }
 }
@@ -0,0 +1,345 @@
+use crate::sync::{AtomicBool, AtomicPtr, AtomicUsize, Mutex, Ordering};
+use crate::{HyperKey, Node};
+use std::ops::Deref;
+use std::ptr;
+
+/// slots per block.
+#[cfg(not(loom))]
+pub(crate) const BLOCK_CAP: usize = 32;
+/// small blocks, so the loom tests cross block boundaries.
+#[cfg(loom)]
+pub(crate) const BLOCK_CAP: usize = 2;
+
+/// blocks kept for reuse by default.
+pub(crate) const POOL_CAP: usize = 4;
+
+/// `BLOCK_CAP` consecutive slots of a `Channel`. the blocks form a linked list,
+/// a block is recycled by the `Pool` once no thread may read it anymore, so
+/// the slots never move while they're in use.
+pub(crate) struct Block<K, V>
+where
+    K: HyperKey + Clone,
+{
+    /// index of the first slot.
+    pub(crate) start: usize,
+    pub(crate) slots: [Node<K, V>; BLOCK_CAP],
+    next: AtomicPtr<Block<K, V>>,
+    /// unlinked while an earlier block was still linked, see
+    /// `Channel::unlink_delivered`. its `next` is kept for the threads
+    /// still reading it.
+    unlinked: AtomicBool,
+}
+
+impl<K, V> Block<K, V>
+where
+    K: HyperKey + Clone,
+{
+    /// a new block of empty slots, freed by the channel.
+    ///
+    /// built in place on the heap, the slots hold the msgs inline, and a
+    /// block of large msgs would overflow the stack.
+    pub(crate) fn alloc(start: usize) -> *mut Self {
+        let mut block = Box::<Self>::new_uninit();
+        let block_ptr = block.as_mut_ptr();
+        unsafe {
+            ptr::addr_of_mut!((*block_ptr).start).write(start);
+            let slots = ptr::addr_of_mut!((*block_ptr).slots).cast::<Node<K, V>>();
+            for index in 0..BLOCK_CAP {
+                Node::init(slots.add(index));
+            }
+            ptr::addr_of_mut!((*block_ptr).next).write(AtomicPtr::new(ptr::null_mut()));
+            ptr::addr_of_mut!((*block_ptr).unlinked).write(AtomicBool::new(false));
+            Box::into_raw(block.assume_init())
+        }
+    }
+
+    /// empties the slots of a block no thread reads anymore, they're all
+    /// delivered so there is no msg to drop.
+    fn reset(&mut self) {
+        for slot in &mut self.slots {
+            slot.reset();
+        }
+        self.next = AtomicPtr::new(ptr::null_mut());
+        self.unlinked = AtomicBool::new(false);
+    }
+
+    /// a sync sender still reads a slot.
+    fn is_pinned(&self) -> bool {
+        self.slots.iter().any(Node::is_pinned)
+    }
+
+    /// every slot is delivered.
+    pub(crate) fn is_delivered(&self) -> bool {
+        self.slots.iter().all(Node::is_delivered)
+    }
+
+    /// `SeqCst`, see `Position::skip_unlinked`.
+    pub(crate) fn is_unlinked(&self) -> bool {
+        self.unlinked.load(Ordering::SeqCst)
+    }
+
+    /// links this block to the one after the next, and returns the next.
+    /// both must exist. called by the receiver holding the pool lock.
+    pub(crate) fn unlink_next(&self) -> *mut Self {
+        let next = self.next();
+        let block = unsafe { &*next };
+        // seen by the threads which move a position onto the block, see
+        // `Position::skip_unlinked`.
+        block.unlinked.store(true, Ordering::SeqCst);
+        self.next.store(block.next(), Ordering::Release);
+        next
+    }
+
+    /// the next block, null until a sender claims a slot in it. `SeqCst`,
+    /// see `Channel::enter`.
+    pub(crate) fn next(&self) -> *mut Self {
+        self.next.load(Ordering::SeqCst)
+    }
+
+    /// the block holding the slot `index`, walking forward from `from` and
+    /// installing the missing blocks, taken from `pool`. `index` is not
+    /// before `from`.
+    ///
+    /// the blocks are passed around as raw pointers, a pointer made from a
+    /// reference couldn't be written through once the block is recycled.
+    pub(crate) fn find(from: *mut Self, index: usize, pool: &Mutex<Pool<K, V>>) -> *mut Self {
+        let mut ptr = from;
+        // the caller keeps the blocks from being recycled.
+        let mut block = unsafe { &*ptr };
+        while index >= block.start + BLOCK_CAP {
+            let mut next = block.next();
+            if next.is_null() {
+                let new = pool.lock().unwrap().take(block.start + BLOCK_CAP);
+                next = match block.next.compare_exchange(
+                    ptr::null_mut(),
+                    new,
+                    Ordering::AcqRel,
+                    Ordering::Acquire,
+                ) {
+                    Ok(_) => new,
+                    // another sender installed it first.
+                    Err(next) => {
+                        pool.lock().unwrap().put(new);
+                        next
+                    }
+                };
+            }
+            ptr = next;
+            block = unsafe { &*ptr };
+        }
+        ptr
+    }
+}
+
+/// Blocks reclaimed from the front of a `Channel`, reused at its back.
+pub(crate) struct Pool<K, V>
+where
+    K: HyperKey + Clone,
+{
+    /// empty blocks, ready to be linked.
+    free: Vec<*mut Block<K, V>>,
+    /// delivered blocks unlinked from the channel, with the epoch they were
+    /// unlinked in. threads which entered the channel before may still read
+    /// them.
+    retired: Vec<(*mut Block<K, V>, usize)>,
+    /// most blocks kept in `free`, the others are freed.
+    cap: usize,
+    /// blocks allocated so far.
+    allocated: usize,
+    /// blocks recycled so far, reused or freed.
+    recycled: usize,
+}
+
+// the pool owns its blocks.
+unsafe impl<K, V> Send for Pool<K, V>
+where
+    K: HyperKey + Clone + Send,
+    V: Send,
+{
+}
+
+impl<K, V> Pool<K, V>
+where
+    K: HyperKey + Clone,
+{
+    pub(crate) fn new(cap: usize) -> Self {
+        Self {
+            free: vec![],
+            retired: vec![],
+            cap,
+            allocated: 0,
+            recycled: 0,
+        }
+    }
+
+    pub(crate) fn allocated(&self) -> usize {
+        self.allocated
+    }
+
+    pub(crate) fn recycled(&self) -> usize {
+        self.recycled
+    }
+
+    /// keeps up to `cap` blocks, allocates `preallocated` of them right now.
+    pub(crate) fn reserve(&mut self, cap: usize, preallocated: usize) {
+        self.cap = cap.max(preallocated);
+        while self.free.len() < preallocated {
+            self.allocated += 1;
+            self.free.push(Block::alloc(0));
+        }
+        while self.free.len() > self.cap {
+            drop(unsafe { Box::from_raw(self.free.pop().unwrap()) });
+        }
+    }
+
+    /// an empty block, reused if possible.
+    pub(crate) fn take(&mut self, start: usize) -> *mut Block<K, V> {
+        let Some(block) = self.free.pop() else {
+            self.allocated += 1;
+            return Block::alloc(start);
+        };
+        // owned by the pool until now.
+        unsafe { (*block).start = start };
+        block
+    }
+
+    /// takes back an empty block no thread reads anymore.
+    fn put(&mut self, block: *mut Block<K, V>) {
+        if self.free.len() < self.cap {
+            self.free.push(block);
+        } else {
+            drop(unsafe { Box::from_raw(block) });
+        }
+    }
+
+    /// takes a block unlinked from the channel in `epoch`, it's reused
+    /// after `recycle`.
+    pub(crate) fn retire(&mut self, block: *mut Block<K, V>, epoch: usize) {
+        self.retired.push((block, epoch));
+    }
+
+    pub(crate) fn has_retired(&self) -> bool {
+        !self.retired.is_empty()
+    }
+
+    /// reuses the blocks retired before `epoch`, no thread which entered
+    /// the channel then is still in. the blocks a sync sender pinned wait
+    /// for it.
+    pub(crate) fn recycle(&mut self, epoch: usize) {
+        let mut index = 0;
+        while index < self.retired.len() {
+            let (block, retired_in) = self.retired[index];
+            // the channel made sure no other thread reads the block.
+            if retired_in >= epoch || unsafe { &*block }.is_pinned() {
+                index += 1;
+                continue;
+            }
+            self.retired.swap_remove(index);
+            unsafe { (*block).reset() };
+            self.recycled += 1;
+            self.put(block);
+        }
+    }
+}
+
+impl<K, V> Drop for Pool<K, V>
+where
+    K: HyperKey + Clone,
+{
+    fn drop(&mut self) {
+        let retired = self.retired.drain(..).map(|(block, _)| block);
+        for block in self.free.drain(..).chain(retired) {
+            drop(unsafe { Box::from_raw(block) });
+        }
+    }
+}
+
+/// Keeps an index hammered by one side of the channel on its own cache line.
+#[repr(align(128))]
+pub(crate) struct CachePadded<T>(pub(crate) T);
+
+impl<T> Deref for CachePadded<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+/// A slot index, and the block holding it or an earlier one.
+///
+/// the index is moved before the block, so whoever loads the block first and
+/// the index next finds the index at or after the start of the block, or in
+/// a delivered block unlinked before it.
+pub(crate) struct Position<K, V>
+where
+    K: HyperKey + Clone,
+{
+    pub(crate) index: AtomicUsize,
+    pub(crate) block: AtomicPtr<Block<K, V>>,
+}
+
+impl<K, V> Position<K, V>
+where
+    K: HyperKey + Clone,
+{
+    pub(crate) fn new(block: *mut Block<K, V>) -> Self {
+        Self {
+            index: AtomicUsize::new(0),
+            block: AtomicPtr::new(block),
+        }
+    }
+
+    /// moves the block forward to `to`, unless it's there or after already.
+    /// `to` must not be after the block of the index.
+    pub(crate) fn catch_up(&self, to: *mut Block<K, V>) {
+        let start = unsafe { &*to }.start;
+        let mut block = self.block.load(Ordering::SeqCst);
+        // the blocks before `to` are still linked, none is recycled yet.
+        while unsafe { &*block }.start < start {
+            match self
+                .block
+                .compare_exchange(block, to, Ordering::SeqCst, Ordering::SeqCst)
+            {
+                Ok(_) => return self.skip_unlinked(),
+                Err(current) => block = current,
+            }
+        }
+    }
+
+    /// moves the block forward from `from` to `to`, unless someone moved it
+    /// already. `to` must not be after the block of the index.
+    pub(crate) fn advance(&self, from: *mut Block<K, V>, to: *mut Block<K, V>) {
+        if from != to
+            && self
+                .block
+                .compare_exchange(from, to, Ordering::SeqCst, Ordering::Relaxed)
+                .is_ok()
+        {
+            self.skip_unlinked();
+        }
+    }
+
+    /// moves the block past the blocks unlinked out of order. a thread which
+    /// found such a block through a stale link may have moved the position
+    /// onto it: either it sees the block unlinked here, or the receiver which
+    /// unlinked it sees the position on it. the caller is in the channel, or
+    /// holds the pool lock.
+    ///
+    /// the moves of the position, its loads and the flag are all `SeqCst`,
+    /// so no fence is needed.
+    pub(crate) fn skip_unlinked(&self) {
+        let mut block = self.block.load(Ordering::SeqCst);
+        while unsafe { &*block }.is_unlinked() {
+            // the block was full, so the index is past it already.
+            let next = unsafe { &*block }.next();
+            match self
+                .block
+                .compare_exchange(block, next, Ordering::SeqCst, Ordering::SeqCst)
+            {
+                Ok(_) => block = next,
+                Err(current) => block = current,
+            }
+        }
+    }
+}
@@ -0,0 +1,205 @@
+use crate::observer::{ChannelObserver, Observer};
+use crate::sync::Arc;
+use crate::{owned_channel, Channel, Fold, HyperKey};
+use std::fmt::Debug;
+use std::thread;
+use std::time::Duration;
+
+/// How far a msg may overtake the msgs sent before it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OrderingMode {
+    /// a msg overtakes the blocked msgs it doesn't collide with, msgs with
+    /// colliding keys keep their order.
+    #[default]
+    PerKey,
+    /// no msg overtakes a blocked one, the receiver waits for the first
+    /// pending msg. priorities only reorder the msgs before it.
+    Strict,
+}
+
+/// What the senders waiting for room or a rendezvous, and the receiver
+/// lingering for a larger batch, do between two attempts. A receiver waiting
+/// for a msg is parked until the channel changes instead.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum WaitStrategy {
+    /// lowest latency, burns a core.
+    #[default]
+    Spin,
+    /// gives the core to other threads.
+    Yield,
+    /// lowest cpu usage, adds up to the given latency.
+    Sleep(Duration),
+}
+
+impl WaitStrategy {
+    pub(crate) fn wait(self) {
+        match self {
+            WaitStrategy::Spin => crate::sync::spin_loop(),
+            WaitStrategy::Yield => crate::sync::yield_now(),
+            WaitStrategy::Sleep(duration) => thread::sleep(duration),
+        }
+    }
+}
+
+/// Configures a `Channel` and builds owned handles to it.
+///
+/// ```ignore
+/// let (tx, rx) = ChannelBuilder::new()
+///     .name("orders")
+///     .capacity(1024)
+///     .wait_strategy(WaitStrategy::Yield)
+///     .build();
+/// ```
+pub struct ChannelBuilder<K, V> {
+    name: Option<String>,
+    capacity: Option<usize>,
+    pool_cap: usize,
+    preallocate: usize,
+    rendezvous_timeout: Duration,
+    ordering: OrderingMode,
+    wait_strategy: WaitStrategy,
+    fold: Option<Fold<V>>,
+    observer: Observer<K>,
+    hot_keys: bool,
+}
+
+impl<K, V> Default for ChannelBuilder<K, V>
+where
+    K: HyperKey + Send + Debug + Clone,
+    V: Send + Debug,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K, V> ChannelBuilder<K, V>
+where
+    K: HyperKey + Send + Debug + Clone,
+    V: Send + Debug,
+{
+    /// Same configuration as `Channel::new`.
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            name: None,
+            capacity: None,
+            pool_cap: crate::block::POOL_CAP * crate::block::BLOCK_CAP,
+            preallocate: 0,
+            rendezvous_timeout: Duration::from_secs(crate::TIME_OUT),
+            ordering: OrderingMode::default(),
+            wait_strategy: WaitStrategy::default(),
+            fold: None,
+            observer: Observer::default(),
+            hot_keys: false,
+        }
+    }
+
+    /// Shows up in `Debug` and in the dumps.
+    #[must_use]
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    /// Senders wait while `capacity` msgs are pending. Unbounded by default.
+    ///
+    /// Room is reserved before a msg is appended, so concurrent senders never
+    /// overshoot it. A `send_all` of more msgs than `capacity` waits for an
+    /// empty channel.
+    #[must_use]
+    pub fn capacity(mut self, capacity: usize) -> Self {
+        self.capacity = Some(capacity);
+        self
+    }
+
+    /// Keeps up to `slots` delivered slots for reuse by later msgs, the
+    /// others are freed. Rounded up to whole blocks of 32 slots, 128 slots by
+    /// default.
+    #[must_use]
+    pub fn pool_cap(mut self, slots: usize) -> Self {
+        self.pool_cap = slots;
+        self
+    }
+
+    /// Allocates room for `slots` msgs upfront, same as
+    /// `Channel::with_capacity`. The pool grows to hold them if needed.
+    #[must_use]
+    pub fn preallocate(mut self, slots: usize) -> Self {
+        self.preallocate = slots;
+        self
+    }
+
+    /// How long a sync sender waits for the delivery of its msgs.
+    #[must_use]
+    pub fn rendezvous_timeout(mut self, timeout: Duration) -> Self {
+        self.rendezvous_timeout = timeout;
+        self
+    }
+
+    #[must_use]
+    pub fn ordering(mut self, ordering: OrderingMode) -> Self {
+        self.ordering = ordering;
+        self
+    }
+
+    #[must_use]
+    pub fn wait_strategy(mut self, wait_strategy: WaitStrategy) -> Self {
+        self.wait_strategy = wait_strategy;
+        self
+    }
+
+    /// Same as `Channel::folding`.
+    #[must_use]
+    pub fn folding(mut self, fold: impl Fn(&mut V, V) + Send + Sync + 'static) -> Self {
+        self.fold = Some(Box::new(fold));
+        self
+    }
+
+    /// Same as `Channel::coalescing`.
+    #[must_use]
+    pub fn coalescing(self) -> Self {
+        self.folding(|old, new| *old = new)
+    }
+
+    /// Same as `Channel::with_observer`.
+    #[must_use]
+    pub fn observer(mut self, observer: impl ChannelObserver<K> + 'static) -> Self {
+        self.observer = Observer::new(std::sync::Arc::new(observer));
+        self
+    }
+
+    /// Same as `Channel::with_hot_keys`.
+    #[must_use]
+    pub fn hot_keys(mut self) -> Self {
+        self.hot_keys = true;
+        self
+    }
+
+    /// Builds the channel alone, to be shared by reference.
+    #[must_use]
+    pub fn build_channel(self) -> Channel<K, V> {
+        let mut chan = Channel::with_fold(self.fold);
+        chan.name = self.name;
+        chan.capacity = self.capacity;
+        chan.reserve(self.pool_cap, self.preallocate);
+        chan.rendezvous_timeout = self.rendezvous_timeout;
+        chan.ordering = self.ordering;
+        chan.wait_strategy = self.wait_strategy;
+        chan.observer = self.observer;
+        if self.hot_keys {
+            chan.shared.filter.track_hot_keys();
+        }
+        chan
+    }
+
+    /// Builds the channel and owned handles to it. The channel is closed
+    /// once every sender is dropped.
+    #[must_use]
+    pub fn build(self) -> (owned_channel::Sender<K, V>, owned_channel::Receiver<K, V>) {
+        let chan = Arc::new(self.build_channel());
+        chan.add_sender();
+        let sender = owned_channel::Sender { chan: chan.clone() };
+        (sender, owned_channel::Receiver { chan })
+    }
+}
@@ -0,0 +1,164 @@
+use std::fmt::{Debug, Write};
+
+/// A snapshot of the slots of a `Channel`, rendered by `Channel::dump`,
+/// `Channel::to_mermaid` and `Channel::to_dot`.
+pub(crate) struct Snapshot<K> {
+    pub(crate) name: Option<String>,
+    pub(crate) closed: bool,
+    /// the written slots still linked, in the order they were claimed. the
+    /// delivered blocks unlinked out of order leave gaps.
+    pub(crate) nodes: Vec<NodeView<K>>,
+    /// index of the slot the scans start from.
+    pub(crate) head: usize,
+    /// index of the next slot to be claimed.
+    pub(crate) tail: usize,
+    /// the keys of the msgs kept aside until they mature, in that order.
+    pub(crate) scheduled: Vec<Vec<K>>,
+    pub(crate) active_keys: Vec<K>,
+}
+
+pub(crate) struct NodeView<K> {
+    pub(crate) index: usize,
+    pub(crate) state: State,
+    /// `None` once the msg is delivered.
+    pub(crate) keys: Option<Vec<K>>,
+    pub(crate) priority: u8,
+}
+
+#[derive(Clone, Copy)]
+pub(crate) enum State {
+    Pending,
+    Delivered,
+}
+
+impl State {
+    fn name(self) -> &'static str {
+        match self {
+            State::Pending => "pending",
+            State::Delivered => "delivered",
+        }
+    }
+}
+
+impl<K: Debug> NodeView<K> {
+    fn id(&self) -> String {
+        format!("node{}", self.index + 1)
+    }
+
+    fn label(&self) -> String {
+        let mut label = format!("#{} {}", self.index + 1, self.state.name());
+        if self.priority > 0 {
+            let _ = write!(label, " p{}", self.priority);
+        }
+        if let Some(keys) = &self.keys {
+            let _ = write!(label, " {keys:?}");
+        }
+        label
+    }
+}
+
+impl<K: Debug> Snapshot<K> {
+    /// the graph node of the slot `index`, `free` past the written slots.
+    fn target(&self, index: usize) -> String {
+        self.nodes
+            .iter()
+            .find(|node| node.index == index)
+            .map_or_else(|| "free".to_owned(), NodeView::id)
+    }
+
+    /// the head, one line per node, then the tail, the scheduled msgs and the
+    /// active keys.
+    pub(crate) fn text(&self) -> String {
+        let name = self
+            .name
+            .as_ref()
+            .map_or_else(String::new, |name| format!(" {name:?}"));
+        let mut text = format!(
+            "Channel{name} ({})\nhead -> #{}\n",
+            if self.closed { "closed" } else { "open" },
+            self.head + 1
+        );
+        for node in &self.nodes {
+            let _ = writeln!(text, "  {}", node.label());
+        }
+        let _ = writeln!(text, "tail -> #{}", self.tail + 1);
+        if !self.scheduled.is_empty() {
+            let _ = writeln!(text, "scheduled: {:?}", self.scheduled);
+        }
+        let _ = writeln!(text, "active keys: {:?}", self.active_keys);
+        text
+    }
+
+    /// a Mermaid flowchart, in the style of the README.
+    pub(crate) fn mermaid(&self) -> String {
+        let mut graph = "graph TD;\n".to_owned();
+        for (i, node) in self.nodes.iter().enumerate() {
+            let label = node.label().replace('"', "#quot;");
+            let _ = writeln!(graph, "    {}[\"{label}\"];", node.id());
+            if i > 0 {
+                let _ = writeln!(graph, "    {}-->{};", self.nodes[i - 1].id(), node.id());
+            }
+        }
+        let _ = writeln!(graph, "    head-->{};", self.target(self.head));
+        let _ = writeln!(graph, "    tail-->{};", self.target(self.tail));
+        if !self.scheduled.is_empty() {
+            graph.push_str("    subgraph scheduled\n");
+            for (index, keys) in self.scheduled.iter().enumerate() {
+                let label = format!("{keys:?}").replace('"', "#quot;");
+                let _ = writeln!(graph, "        timer{}[\"{label}\"];", index + 1);
+            }
+            graph.push_str("    end\n");
+        }
+        if !self.active_keys.is_empty() {
+            graph.push_str("    subgraph active_keys\n");
+            for (index, key) in self.active_keys.iter().enumerate() {
+                let label = format!("{key:?}").replace('"', "#quot;");
+                let _ = writeln!(graph, "        key{}[\"{label}\"];", index + 1);
+            }
+            graph.push_str("    end\n");
+        }
+        graph
+    }
+
+    /// a Graphviz digraph.
+    pub(crate) fn dot(&self) -> String {
+        let mut graph = "digraph channel {\n    rankdir=LR;\n    node [shape=box];\n".to_owned();
+        graph.push_str("    head [shape=plaintext];\n    tail [shape=plaintext];\n");
+        for (i, node) in self.nodes.iter().enumerate() {
+            let id = node.id();
+            let label = node.label().replace('"', "\\\"");
+            let style = match node.state {
+                State::Delivered => ", style=dashed",
+                State::Pending => "",
+            };
+            let _ = writeln!(graph, "    {id} [label=\"{label}\"{style}];");
+            if i > 0 {
+                let _ = writeln!(graph, "    {} -> {id};", self.nodes[i - 1].id());
+            }
+        }
+        let _ = writeln!(graph, "    head -> {};", self.target(self.head));
+        let _ = writeln!(graph, "    tail -> {};", self.target(self.tail));
+        if !self.scheduled.is_empty() {
+            graph.push_str("    subgraph cluster_scheduled {\n        label=\"scheduled\";\n");
+            for (index, keys) in self.scheduled.iter().enumerate() {
+                let label = format!("{keys:?}").replace('"', "\\\"");
+                let _ = writeln!(
+                    graph,
+                    "        timer{} [label=\"{label}\", style=dotted];",
+                    index + 1
+                );
+            }
+            graph.push_str("    }\n");
+        }
+        if !self.active_keys.is_empty() {
+            graph.push_str("    subgraph cluster_active_keys {\n        label=\"active keys\";\n");
+            for (index, key) in self.active_keys.iter().enumerate() {
+                let label = format!("{key:?}").replace('"', "\\\"");
+                let _ = writeln!(graph, "        key{} [label=\"{label}\"];", index + 1);
+            }
+            graph.push_str("    }\n");
+        }
+        graph.push_str("}\n");
+        graph
+    }
+}
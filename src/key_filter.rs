@@ -1,53 +1,325 @@
-use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
+use crate::sync::{Arc, Mutex};
 use crate::HyperKey;
 
+/// number of keys whose contention is tracked, the least contended one makes
+/// room for a new key.
+pub(crate) const HOT_KEYS_CAP: usize = 64;
+
 #[derive(Debug)]
 pub(crate) struct Filter<K: HyperKey + Clone> {
-    active_keys: Arc<Mutex<Vec<K>>>,
+    state: Arc<Mutex<State<K>>>,
+}
+
+#[derive(Debug)]
+struct State<K> {
+    active_keys: Vec<Active<K>>,
+    /// contention of the most contended keys, up to `HOT_KEYS_CAP`. `None`
+    /// unless tracked, see `track_hot_keys`.
+    hot_keys: Option<Vec<HotKey<K>>>,
+    /// running watchdogs, the keys of the msgs are kept for them only.
+    watchdogs: usize,
+}
+
+#[derive(Debug)]
+struct Active<K> {
+    key: K,
+    since: Instant,
+    /// every key of the msg which acquired this one, while watched.
+    msg_keys: Vec<K>,
+    enqueued_at: Instant,
+    /// the watchdog already reported this hold.
+    reported: bool,
+}
+
+/// Contention of a key, see `Channel::hot_keys`.
+#[derive(Debug, Clone)]
+pub struct HotKey<K> {
+    pub key: K,
+    /// pending msgs which had to wait for this key, each counted once
+    /// while it stays blocked.
+    pub skips: usize,
+    /// times the key was acquired by a delivered msg.
+    pub acquisitions: usize,
+    /// total time the key was held, the ongoing hold included.
+    pub hold_time: Duration,
+    /// the key is held right now.
+    pub held: bool,
+    /// pending msgs colliding with this key right now.
+    pub queue_depth: usize,
+}
+
+/// An active key blocking a msg, see `Channel::blockers`.
+#[derive(Debug, Clone)]
+pub struct Blocker<K> {
+    pub key: K,
+    /// time since the key was acquired.
+    pub held_for: Duration,
+}
+
+/// A key held longer than the watchdog threshold, see `Channel::watchdog`.
+#[derive(Debug, Clone)]
+pub struct StuckKey<K> {
+    pub key: K,
+    /// every key of the msg holding it, empty if the msg was delivered
+    /// before the watchdog was spawned.
+    pub msg_keys: Vec<K>,
+    /// when the msg was sent.
+    pub enqueued_at: Instant,
+    /// when the msg was delivered, the key is held since then.
+    pub delivered_at: Instant,
+    pub held_for: Duration,
+}
+
+/// A pending msg which can't be delivered right now, see
+/// `Channel::blocked_msgs`.
+#[derive(Debug, Clone)]
+pub struct BlockedMsg<K> {
+    pub keys: Vec<K>,
+    /// position among the pending msgs, 0 is the oldest.
+    pub position: usize,
+    /// the active keys colliding with `keys`.
+    pub blockers: Vec<Blocker<K>>,
+    /// an earlier pending msg has colliding keys, the msg can't overtake it.
+    pub behind_earlier: bool,
+}
+
+/// the stats of `key`, created the first time the key is seen. once the
+/// table is full they replace the stats of the least contended key.
+fn hot_key<'a, K: HyperKey + Clone>(
+    hot_keys: &'a mut Vec<HotKey<K>>,
+    key: &K,
+) -> &'a mut HotKey<K> {
+    let found = hot_keys
+        .iter()
+        .position(|elem| elem.key.collision_detect(key));
+    let index = if let Some(index) = found {
+        index
+    } else {
+        let hot_key = HotKey {
+            key: key.clone(),
+            skips: 0,
+            acquisitions: 0,
+            hold_time: Duration::ZERO,
+            held: false,
+            queue_depth: 0,
+        };
+        if hot_keys.len() < HOT_KEYS_CAP {
+            hot_keys.push(hot_key);
+            hot_keys.len() - 1
+        } else {
+            let (index, _) = hot_keys
+                .iter()
+                .enumerate()
+                .min_by_key(|(_, elem)| (elem.skips, elem.hold_time))
+                .unwrap();
+            hot_keys[index] = hot_key;
+            index
+        }
+    };
+    &mut hot_keys[index]
 }
 
 impl<K: HyperKey + Clone> Filter<K> {
-    pub(crate) fn contains(&self, k: &Vec<K>) -> bool {
-        let res: Vec<bool> = (0..k.len())
-            .map(|index| {
-                self.active_keys
-                    .lock()
-                    .unwrap()
-                    .iter()
-                    .any(|elem| k[index].collision_detect(elem))
+    /// starts tracking the contention of the keys, for `hot_keys`.
+    pub(crate) fn track_hot_keys(&self) {
+        self.state
+            .lock()
+            .unwrap()
+            .hot_keys
+            .get_or_insert_with(Vec::new);
+    }
+
+    /// a watchdog starts, the keys of the msgs delivered from now on are
+    /// kept for its reports.
+    pub(crate) fn watch(&self) {
+        self.state.lock().unwrap().watchdogs += 1;
+    }
+
+    /// a watchdog stops.
+    pub(crate) fn unwatch(&self) {
+        self.state.lock().unwrap().watchdogs -= 1;
+    }
+
+    pub(crate) fn contains(&self, k: &[K]) -> bool {
+        let state = self.state.lock().unwrap();
+        k.iter().any(|key| {
+            state
+                .active_keys
+                .iter()
+                .any(|elem| key.collision_detect(&elem.key))
+        })
+    }
+
+    /// a pending msg started waiting for `blockers`, a skip is counted for
+    /// each of them.
+    pub(crate) fn record_skips(&self, blockers: &[K]) {
+        let mut state = self.state.lock().unwrap();
+        let Some(hot_keys) = &mut state.hot_keys else {
+            return;
+        };
+        for key in blockers {
+            hot_key(hot_keys, key).skips += 1;
+        }
+    }
+
+    /// the active keys colliding with `k`, and how long they have been held.
+    pub(crate) fn blockers(&self, k: &[K]) -> Vec<Blocker<K>> {
+        let state = self.state.lock().unwrap();
+        let now = Instant::now();
+        state
+            .active_keys
+            .iter()
+            .filter(|elem| k.iter().any(|key| key.collision_detect(&elem.key)))
+            .map(|elem| Blocker {
+                key: elem.key.clone(),
+                held_for: now - elem.since,
             })
-            .collect();
+            .collect()
+    }
 
-        res.iter().any(|elem| *elem)
+    /// number of active keys.
+    pub(crate) fn len(&self) -> usize {
+        self.state.lock().unwrap().active_keys.len()
     }
 
-    pub(crate) fn put(&self, k: &[K]) {
-        self.active_keys.lock().unwrap().append(&mut Vec::from(k));
+    /// the active keys, in the order they were acquired.
+    pub(crate) fn active_keys(&self) -> Vec<K> {
+        let state = self.state.lock().unwrap();
+        state
+            .active_keys
+            .iter()
+            .map(|elem| elem.key.clone())
+            .collect()
+    }
+
+    /// registers the keys of a msg sent at `enqueued_at`.
+    pub(crate) fn put(&self, k: &[K], enqueued_at: Instant) {
+        self.put_all([(k, enqueued_at)]);
+    }
+
+    /// registers the keys of several msgs at once.
+    pub(crate) fn put_all<'a>(&self, msgs: impl IntoIterator<Item = (&'a [K], Instant)>)
+    where
+        K: 'a,
+    {
+        let mut state = self.state.lock().unwrap();
+        let state = &mut *state;
+        let since = Instant::now();
+        for (k, enqueued_at) in msgs {
+            for key in k {
+                if let Some(hot_keys) = &mut state.hot_keys {
+                    hot_key(hot_keys, key).acquisitions += 1;
+                }
+                state.active_keys.push(Active {
+                    key: key.clone(),
+                    since,
+                    msg_keys: if state.watchdogs > 0 {
+                        k.to_vec()
+                    } else {
+                        vec![]
+                    },
+                    enqueued_at,
+                    reported: false,
+                });
+            }
+        }
     }
 
     pub(crate) fn pop(&self, k: &[K]) {
-        let mut guard = self.active_keys.lock().unwrap();
-        let _indexes: Vec<_> = k
+        let mut state = self.state.lock().unwrap();
+        for key in k {
+            let index = state
+                .active_keys
+                .iter()
+                .position(|elem| elem.key.collision_detect(key))
+                .unwrap();
+            let active = state.active_keys.remove(index);
+            if let Some(hot_keys) = &mut state.hot_keys {
+                hot_key(hot_keys, &active.key).hold_time += active.since.elapsed();
+            }
+        }
+    }
+
+    /// the keys held for `threshold` or more which were not reported yet,
+    /// each hold is reported once.
+    pub(crate) fn stuck(&self, threshold: Duration) -> Vec<StuckKey<K>> {
+        let mut state = self.state.lock().unwrap();
+        let now = Instant::now();
+        state
+            .active_keys
+            .iter_mut()
+            .filter(|elem| !elem.reported && now - elem.since >= threshold)
+            .map(|elem| {
+                elem.reported = true;
+                StuckKey {
+                    key: elem.key.clone(),
+                    msg_keys: elem.msg_keys.clone(),
+                    enqueued_at: elem.enqueued_at,
+                    delivered_at: elem.since,
+                    held_for: now - elem.since,
+                }
+            })
+            .collect()
+    }
+
+    /// the `n` keys which made pending msgs skip the most, `queue_depth` is
+    /// left to the channel. empty unless tracked.
+    pub(crate) fn hot_keys(&self, n: usize) -> Vec<HotKey<K>> {
+        let state = self.state.lock().unwrap();
+        let now = Instant::now();
+        let mut hot_keys: Vec<HotKey<K>> = state
+            .hot_keys
             .iter()
-            .map(|key| {
-                let index = guard
-                    .iter()
-                    .position(|elem| elem.collision_detect(key))
-                    .unwrap();
-                guard.remove(index);
+            .flatten()
+            .map(|hot_key| {
+                let mut hot_key = hot_key.clone();
+                for active in &state.active_keys {
+                    if active.key.collision_detect(&hot_key.key) {
+                        hot_key.held = true;
+                        hot_key.hold_time += now - active.since;
+                    }
+                }
+                hot_key
             })
             .collect();
+        hot_keys.sort_by(|a, b| {
+            b.skips
+                .cmp(&a.skips)
+                .then_with(|| b.hold_time.cmp(&a.hold_time))
+        });
+        hot_keys.truncate(n);
+        hot_keys
     }
 }
 
+/// the keys of `active` colliding with `k`.
+pub(crate) fn blocking_keys<K: HyperKey + Clone>(active: &[K], k: &[K]) -> Vec<K> {
+    active
+        .iter()
+        .filter(|elem| k.iter().any(|key| key.collision_detect(elem)))
+        .cloned()
+        .collect()
+}
+
+/// checks whether any key of `a` collides with any key of `b`.
+pub(crate) fn collides<K: HyperKey>(a: &[K], b: &[K]) -> bool {
+    b.iter()
+        .any(|key| a.iter().any(|elem| key.collision_detect(elem)))
+}
+
 impl<K> Default for Filter<K>
 where
     K: HyperKey + Clone,
 {
     fn default() -> Self {
         Self {
-            active_keys: std::sync::Arc::default(),
+            state: Arc::new(Mutex::new(State {
+                active_keys: vec![],
+                hot_keys: None,
+                watchdogs: 0,
+            })),
         }
     }
 }
@@ -58,16 +330,18 @@ where
 {
     fn clone(&self) -> Self {
         Self {
-            active_keys: self.active_keys.clone(),
+            state: self.state.clone(),
         }
     }
 }
 
+#[cfg(test)]
 #[derive(Clone)]
 struct SimpleKey {
     key: usize,
 }
 
+#[cfg(test)]
 impl HyperKey for SimpleKey {
     fn collision_detect(&self, other: &Self) -> bool {
         self.key == other.key
@@ -79,11 +353,11 @@ fn single_test() {
     let filter = Filter::default();
     let mut keys = vec![];
     for i in 1..=100 {
-        keys.push(SimpleKey { key: i as usize });
+        keys.push(SimpleKey { key: i });
     }
-    filter.put(&keys);
+    filter.put(&keys, Instant::now());
     for i in 1..=100 {
-        assert!(filter.contains(&vec![SimpleKey { key: i as usize }]));
+        assert!(filter.contains(&[SimpleKey { key: i }]));
     }
 }
 
@@ -92,14 +366,14 @@ fn multikeys_test() {
     let filter = Filter::default();
     let mut keys = vec![];
     for i in 1..=100 {
-        keys.push(SimpleKey { key: i as usize });
+        keys.push(SimpleKey { key: i });
     }
-    filter.put(&keys);
+    filter.put(&keys, Instant::now());
 
     for i in 1..=100 {
         let mut temp_keys = vec![];
         for j in 1..=i {
-            temp_keys.push(SimpleKey { key: j as usize });
+            temp_keys.push(SimpleKey { key: j });
         }
         assert!(filter.contains(&temp_keys));
     }
@@ -110,22 +384,22 @@ fn pop_test() {
     let filter = Filter::default();
     let mut keys = vec![];
     for i in 1..=100 {
-        keys.push(SimpleKey { key: i as usize });
+        keys.push(SimpleKey { key: i });
     }
-    filter.put(&keys);
+    filter.put(&keys, Instant::now());
 
     // pop one.
     for i in 1..=100 {
-        filter.pop(&vec![SimpleKey { key: i as usize }]);
-        assert!(!filter.contains(&vec![SimpleKey { key: i as usize }]));
+        filter.pop(&[SimpleKey { key: i }]);
+        assert!(!filter.contains(&[SimpleKey { key: i }]));
     }
 
     // pop many.
-    filter.put(&keys);
+    filter.put(&keys, Instant::now());
     for i in (1..=100).step_by(10) {
         let mut temp = vec![];
         for j in i..i + 10 {
-            temp.push(SimpleKey { key: j as usize });
+            temp.push(SimpleKey { key: j });
         }
         filter.pop(&temp);
         assert!(!filter.contains(&temp), "failed to pop many");
@@ -140,12 +414,108 @@ fn multithreads_test() {
     for i in 1..=10 {
         let filter = filter.clone();
         joins.push(std::thread::spawn(move || {
-            filter.put(&vec![SimpleKey { key: i as usize }])
+            filter.put(&[SimpleKey { key: i }], Instant::now());
         }));
     }
-    let _join: Vec<_> = joins.into_iter().map(|handler| handler.join()).collect();
+    let _join: Vec<_> = joins
+        .into_iter()
+        .map(std::thread::JoinHandle::join)
+        .collect();
 
     for i in 1..=10 {
-        assert!(filter.contains(&vec![SimpleKey { key: i as usize }]));
+        assert!(filter.contains(&[SimpleKey { key: i }]));
     }
 }
+
+#[test]
+fn blockers_test() {
+    let filter = Filter::default();
+    filter.put(&[SimpleKey { key: 1 }], Instant::now());
+    filter.put(&[SimpleKey { key: 2 }], Instant::now());
+
+    let blockers = filter.blockers(&[SimpleKey { key: 2 }, SimpleKey { key: 3 }]);
+    assert_eq!(blockers.len(), 1);
+    assert_eq!(blockers[0].key.key, 2);
+    assert!(filter.blockers(&[SimpleKey { key: 3 }]).is_empty());
+}
+
+#[test]
+fn stuck_test() {
+    let filter = Filter::default();
+    filter.watch();
+    let enqueued_at = Instant::now();
+    filter.put(&[SimpleKey { key: 1 }, SimpleKey { key: 2 }], enqueued_at);
+    assert!(filter.stuck(Duration::from_secs(10)).is_empty());
+
+    let stuck = filter.stuck(Duration::ZERO);
+    assert_eq!(stuck.len(), 2);
+    assert_eq!(stuck[0].key.key, 1);
+    assert_eq!(stuck[0].msg_keys.len(), 2);
+    assert_eq!(stuck[0].enqueued_at, enqueued_at);
+    // reported once.
+    assert!(filter.stuck(Duration::ZERO).is_empty());
+}
+
+#[test]
+fn hot_keys_test() {
+    let filter = Filter::default();
+    filter.track_hot_keys();
+    filter.put(
+        &[SimpleKey { key: 1 }, SimpleKey { key: 2 }],
+        Instant::now(),
+    );
+    for _ in 0..3 {
+        let blockers = blocking_keys(&filter.active_keys(), &[SimpleKey { key: 1 }]);
+        assert_eq!(blockers.len(), 1);
+        filter.record_skips(&blockers);
+    }
+    let blockers = blocking_keys(
+        &filter.active_keys(),
+        &[SimpleKey { key: 2 }, SimpleKey { key: 3 }],
+    );
+    assert_eq!(blockers.len(), 1);
+    filter.record_skips(&blockers);
+    assert!(blocking_keys(&filter.active_keys(), &[SimpleKey { key: 3 }]).is_empty());
+    filter.pop(&[SimpleKey { key: 2 }]);
+
+    let hot_keys = filter.hot_keys(10);
+    assert_eq!(hot_keys.len(), 2);
+    assert_eq!((hot_keys[0].key.key, hot_keys[0].skips), (1, 3));
+    assert_eq!((hot_keys[1].key.key, hot_keys[1].skips), (2, 1));
+    assert!(hot_keys[0].held);
+    assert!(!hot_keys[1].held);
+    assert_eq!(hot_keys[1].acquisitions, 1);
+    assert_eq!(filter.hot_keys(1).len(), 1);
+}
+
+#[test]
+fn hot_keys_cap_test() {
+    let filter = Filter::default();
+    filter.track_hot_keys();
+    filter.put(&[SimpleKey { key: 0 }], Instant::now());
+    filter.record_skips(&[SimpleKey { key: 0 }]);
+    for i in 1..=2 * HOT_KEYS_CAP {
+        filter.put(&[SimpleKey { key: i }], Instant::now());
+    }
+
+    // the contended key is kept, the others make room for each other.
+    let hot_keys = filter.hot_keys(usize::MAX);
+    assert_eq!(hot_keys.len(), HOT_KEYS_CAP);
+    assert_eq!((hot_keys[0].key.key, hot_keys[0].skips), (0, 1));
+}
+
+#[test]
+fn untracked_test() {
+    let filter = Filter::default();
+    filter.put(
+        &[SimpleKey { key: 1 }, SimpleKey { key: 2 }],
+        Instant::now(),
+    );
+    filter.record_skips(&[SimpleKey { key: 1 }]);
+    assert!(filter.hot_keys(10).is_empty());
+
+    // the keys of the msg are not kept without a watchdog.
+    let stuck = filter.stuck(Duration::ZERO);
+    assert_eq!(stuck.len(), 2);
+    assert!(stuck[0].msg_keys.is_empty());
+}
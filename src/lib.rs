@@ -1,269 +1,1858 @@
 #![deny(clippy::all, clippy::pedantic, clippy::cargo)]
 
+pub mod actor;
 pub mod async_channel;
+mod block;
+pub mod builder;
+mod dump;
 pub mod key_filter;
+pub mod observer;
+pub mod owned_channel;
+pub mod select;
+mod signal;
+pub mod stats;
+mod sync;
 pub mod sync_channel;
+pub mod watchdog;
 
+use std::cell::UnsafeCell;
+use std::collections::BTreeMap;
 use std::fmt::Debug;
+use std::marker::PhantomData;
+use std::mem::{ManuallyDrop, MaybeUninit};
 use std::ptr;
-use std::sync::atomic::{AtomicBool, AtomicPtr, Ordering};
 use std::time::{Duration, Instant};
+use sync::{fence, Arc, AtomicBool, AtomicPtr, AtomicU8, AtomicUsize, Mutex, Ordering};
 
 const TIME_OUT: u64 = 20;
 
+/// emits a `tracing` event when the `tracing` feature is on.
+macro_rules! trace_event {
+    ($level:ident, $($arg:tt)*) => {
+        #[cfg(feature = "tracing")]
+        tracing::$level!(target: "mpsc", $($arg)*);
+    };
+}
+
 #[derive(Debug, Clone)]
 pub struct SendError;
 
-#[derive(Debug, Clone)]
-pub struct RecvError;
+#[derive(Debug, Clone)]
+pub struct RecvError;
+
+/// Channel acts as a linked-list to hold the msg.
+pub struct Channel<K, V>
+where
+    K: Clone + HyperKey,
+{
+    /// `AtomicPtr` impls Send + Sync, so Channel is Send + Sync by default.
+    /// the first block still linked, moved by `reclaim`.
+    first: AtomicPtr<block::Block<K, V>>,
+    /// where the scans start, every slot before it is delivered.
+    head: block::CachePadded<block::Position<K, V>>,
+    /// the next slot to be claimed by a sender.
+    tail: block::CachePadded<block::Position<K, V>>,
+    /// bumped by `reclaim` once the threads which entered in the previous
+    /// epoch are gone.
+    epoch: AtomicUsize,
+    /// threads which may read the blocks right now, by the parity of the
+    /// epoch they entered in, see `enter`.
+    users: [AtomicUsize; 2],
+    /// the blocks reclaimed by the receiver, reused by the senders.
+    pool: Mutex<block::Pool<K, V>>,
+    /// the msgs scheduled later, out of the slots until they mature so they
+    /// don't hold the head back.
+    timers: Mutex<Timers<K, V>>,
+    /// number of msgs in `timers`, checked before taking the lock.
+    scheduled: AtomicUsize,
+    /// the active keys, the counters and the parked receivers, shared with
+    /// the msgs which release their keys.
+    shared: Arc<Shared<K>>,
+    /// set by `close`, no more msgs will be sent.
+    closed: AtomicBool,
+    /// senders alive, borrowed or owned, the last one closes the channel.
+    senders: AtomicUsize,
+    /// set when the owned receiver is dropped, no more msgs will be received.
+    disconnected: AtomicBool,
+    /// set once a msg with a non-default priority is sent.
+    prioritized: AtomicBool,
+    /// folds a new msg into the pending msg with the same keys.
+    fold: Option<Fold<V>>,
+    observer: observer::Observer<K>,
+    name: Option<String>,
+    /// senders wait while this many msgs are pending.
+    capacity: Option<usize>,
+    /// msgs counted against `capacity`: room is reserved before they're
+    /// appended, and given back once they're delivered or folded.
+    reserved: AtomicUsize,
+    /// how long a sync sender waits for the delivery of its msgs.
+    rendezvous_timeout: Duration,
+    ordering: builder::OrderingMode,
+    wait_strategy: builder::WaitStrategy,
+    /// the channel owns its blocks and the msgs in them.
+    _blocks: PhantomData<Box<block::Block<K, V>>>,
+}
+
+// nodes are shared through raw pointers, a msg is only borrowed by the thread
+// which locked its node, so `K` and `V` don't need to be `Sync`.
+unsafe impl<K, V> Sync for Channel<K, V>
+where
+    K: HyperKey + Clone + Send,
+    V: Send,
+{
+}
+
+type Fold<V> = Box<dyn Fn(&mut V, V) + Send + Sync>;
+
+/// the scheduled msgs by the instant they mature at, and the order they were
+/// sent in.
+type Timers<K, V> = BTreeMap<(Instant, usize), Timer<K, V>>;
+
+impl<K, V> Default for Channel<K, V>
+where
+    K: HyperKey + Send + Debug + Clone,
+    V: Send + Debug,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K, V> Channel<K, V>
+where
+    K: HyperKey + Send + Debug + Clone,
+    V: Send + Debug,
+{
+    /// Creates a channel, its slots are allocated block by block.
+    ///
+    /// # Channel head ---   tail -----------
+    /// #                |                  |
+    /// #                |                  |
+    /// #  | slot | slot | slot | .. | slot | ---> next block
+    ///
+    #[must_use]
+    pub fn new() -> Channel<K, V> {
+        Self::with_fold(None)
+    }
+
+    /// Creates a channel with room for `slots` msgs allocated upfront. The
+    /// room is reused once the msgs are delivered, so sending up to `slots`
+    /// pending msgs doesn't allocate.
+    #[must_use]
+    pub fn with_capacity(slots: usize) -> Channel<K, V> {
+        let chan = Self::new();
+        chan.reserve(block::POOL_CAP * block::BLOCK_CAP, slots);
+        chan
+    }
+
+    /// keeps up to `cap` slots for reuse, `preallocated` of them allocated
+    /// right now. both are rounded up to whole blocks.
+    pub(crate) fn reserve(&self, cap: usize, preallocated: usize) {
+        self.pool.lock().unwrap().reserve(
+            cap.div_ceil(block::BLOCK_CAP),
+            preallocated.div_ceil(block::BLOCK_CAP),
+        );
+    }
+
+    /// Creates a channel where a msg replaces the pending msg with the same
+    /// keys, only the latest value of a key is delivered.
+    #[must_use]
+    pub fn coalescing() -> Channel<K, V> {
+        Self::folding(|old, new| *old = new)
+    }
+
+    /// Creates a channel where a msg is merged by `fold` into the pending msg
+    /// with the same keys, unless that msg is scheduled earlier or has a
+    /// lower priority than the new one.
+    #[must_use]
+    pub fn folding(fold: impl Fn(&mut V, V) + Send + Sync + 'static) -> Channel<K, V> {
+        Self::with_fold(Some(Box::new(fold)))
+    }
+
+    fn with_fold(fold: Option<Fold<V>>) -> Channel<K, V> {
+        let mut pool = block::Pool::new(block::POOL_CAP);
+        let first = pool.take(0);
+        Self {
+            first: AtomicPtr::new(first),
+            head: block::CachePadded(block::Position::new(first)),
+            tail: block::CachePadded(block::Position::new(first)),
+            epoch: AtomicUsize::new(0),
+            users: [AtomicUsize::new(0), AtomicUsize::new(0)],
+            pool: Mutex::new(pool),
+            timers: Mutex::default(),
+            scheduled: AtomicUsize::new(0),
+            shared: Arc::new(Shared {
+                filter: key_filter::Filter::default(),
+                counters: stats::Counters::default(),
+                signal: signal::Signal::default(),
+            }),
+            closed: AtomicBool::new(false),
+            senders: AtomicUsize::new(0),
+            disconnected: AtomicBool::new(false),
+            prioritized: AtomicBool::new(false),
+            fold,
+            observer: observer::Observer::default(),
+            name: None,
+            capacity: None,
+            reserved: AtomicUsize::new(0),
+            rendezvous_timeout: Duration::from_secs(TIME_OUT),
+            ordering: builder::OrderingMode::default(),
+            wait_strategy: builder::WaitStrategy::default(),
+            _blocks: PhantomData,
+        }
+    }
+
+    /// Registers `observer` to be called along the lifecycle of every msg.
+    #[must_use]
+    pub fn with_observer(mut self, observer: impl observer::ChannelObserver<K> + 'static) -> Self {
+        self.observer = observer::Observer::new(std::sync::Arc::new(observer));
+        self
+    }
+
+    /// Tracks the contention of the keys, reported by `hot_keys`. It costs a
+    /// lookup in the table of the hot keys on every delivery and release.
+    #[must_use]
+    pub fn with_hot_keys(self) -> Self {
+        self.shared.filter.track_hot_keys();
+        self
+    }
+
+    /// Marks the channel as disconnected: once every pending msg is
+    /// delivered, blocking receivers return `RecvError` and iterators end.
+    pub fn close(&self) {
+        self.closed.store(true, Ordering::Release);
+        self.shared.signal.notify();
+    }
+
+    #[must_use]
+    pub fn is_closed(&self) -> bool {
+        self.closed.load(Ordering::Acquire)
+    }
+
+    /// The name given by `ChannelBuilder::name`.
+    #[must_use]
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// Takes a snapshot of the counters, the pending msgs are not scanned.
+    #[must_use]
+    pub fn stats(&self) -> stats::Stats {
+        // loaded first, so a concurrent delivery can't make `pending` underflow.
+        let delivered = self.shared.counters.delivered.load(Ordering::Relaxed);
+        let sent = self.shared.counters.sent.load(Ordering::Relaxed);
+        let coalesced = self.shared.counters.coalesced.load(Ordering::Relaxed);
+        let (allocated, recycled) = {
+            let pool = self
+                .pool
+                .lock()
+                .unwrap_or_else(std::sync::PoisonError::into_inner);
+            (pool.allocated(), pool.recycled())
+        };
+        stats::Stats {
+            pending: sent.saturating_sub(coalesced).saturating_sub(delivered),
+            blocked: self.shared.counters.blocked_msgs.load(Ordering::Relaxed),
+            active_keys: self.shared.filter.len(),
+            sent,
+            coalesced,
+            delivered,
+            released: self.shared.counters.released.load(Ordering::Relaxed),
+            // every slot of a recycled block was delivered.
+            awaiting_reclamation: delivered.saturating_sub(recycled * block::BLOCK_CAP),
+            allocated_blocks: allocated,
+        }
+    }
+
+    /// Snapshots the latency histograms of the msgs delivered and released
+    /// so far.
+    #[must_use]
+    pub fn latencies(&self) -> stats::Latencies {
+        stats::Latencies {
+            queueing: self.shared.counters.queueing.snapshot(),
+            blocked: self.shared.counters.blocked.snapshot(),
+            hold: self.shared.counters.hold.snapshot(),
+        }
+    }
+
+    /// Snapshots the keys held by the delivered msgs which are not dropped
+    /// yet.
+    #[must_use]
+    pub fn active_keys(&self) -> Vec<K> {
+        self.shared.filter.active_keys()
+    }
+
+    /// Checks whether `key` collides with an active key.
+    #[must_use]
+    pub fn is_key_active(&self, key: &K) -> bool {
+        self.shared.filter.contains(std::slice::from_ref(key))
+    }
+
+    /// Number of msgs sent but not delivered yet, scheduled ones included.
+    #[must_use]
+    pub fn pending_len(&self) -> usize {
+        let queued = self
+            .nodes()
+            .filter(|node| node.with_msg(|_| ()).is_some())
+            .count();
+        let timers = self
+            .timers
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        queued + timers.len()
+    }
+
+    /// Iterates over the keys of the pending msgs, in the order they were
+    /// queued, then over the keys of the msgs scheduled later, in the order
+    /// they mature. The keys are collected upfront, so a live iterator
+    /// doesn't keep the delivered blocks from being reused.
+    pub fn pending_keys(&self) -> impl Iterator<Item = Vec<K>> + '_ {
+        let mut keys: Vec<_> = self
+            .nodes()
+            .filter_map(|node| node.with_msg(|msg| msg.keys.clone()))
+            .collect();
+        keys.extend(self.scheduled_keys());
+        keys.into_iter()
+    }
+
+    /// the keys of the msgs scheduled later, in the order they mature.
+    fn scheduled_keys(&self) -> Vec<Vec<K>> {
+        let timers = self
+            .timers
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        timers.values().map(|timer| timer.keys.clone()).collect()
+    }
+
+    /// Reports the active keys colliding with `keys`, and how long they have
+    /// been held. A msg with `keys` can't be delivered until they're released.
+    #[must_use]
+    pub fn blockers(&self, keys: &[K]) -> Vec<key_filter::Blocker<K>> {
+        self.shared.filter.blockers(keys)
+    }
+
+    /// Explains every pending msg the receiver skips right now: the active
+    /// keys it collides with, and whether an earlier pending msg holds it back.
+    ///
+    /// Scheduled msgs are left out until they mature.
+    #[must_use]
+    pub fn blocked_msgs(&self) -> Vec<key_filter::BlockedMsg<K>> {
+        let now = Instant::now();
+        let mut blocked = vec![];
+        let mut earlier: Vec<K> = vec![];
+        let pending = self
+            .nodes()
+            .filter_map(|node| node.with_msg(|msg| (msg.keys.clone(), node.is_visible(now))));
+        for (position, (keys, visible)) in pending.enumerate() {
+            if !visible {
+                continue;
+            }
+            let blockers = self.shared.filter.blockers(&keys);
+            let behind_earlier = key_filter::collides(&earlier, &keys);
+            earlier.extend_from_slice(&keys);
+            if !blockers.is_empty() || behind_earlier {
+                blocked.push(key_filter::BlockedMsg {
+                    keys,
+                    position,
+                    blockers,
+                    behind_earlier,
+                });
+            }
+        }
+        blocked
+    }
+
+    /// Renders the slots as text: every written slot with its state and keys,
+    /// the head and the tail, and the active keys.
+    #[must_use]
+    pub fn dump(&self) -> String {
+        self.snapshot().text()
+    }
+
+    /// Same as `dump`, as a Mermaid flowchart.
+    #[must_use]
+    pub fn to_mermaid(&self) -> String {
+        self.snapshot().mermaid()
+    }
+
+    /// Same as `dump`, as a Graphviz digraph.
+    #[must_use]
+    pub fn to_dot(&self) -> String {
+        self.snapshot().dot()
+    }
+
+    /// walks the nodes once, msgs delivered during the walk may show either way.
+    fn snapshot(&self) -> dump::Snapshot<K> {
+        let mut all_nodes = self.all_nodes();
+        let mut nodes = vec![];
+        while let Some(node) = all_nodes.peek() {
+            let keys = node.with_msg(|msg| msg.keys.clone());
+            let state = if keys.is_none() {
+                dump::State::Delivered
+            } else {
+                dump::State::Pending
+            };
+            nodes.push(dump::NodeView {
+                index: all_nodes.index,
+                state,
+                keys,
+                priority: node.meta().priority,
+            });
+            all_nodes.index += 1;
+        }
+        dump::Snapshot {
+            name: self.name.clone(),
+            closed: self.is_closed(),
+            nodes,
+            head: self.head.index.load(Ordering::Acquire),
+            tail: self.tail.index.load(Ordering::Acquire),
+            scheduled: self.scheduled_keys(),
+            active_keys: self.active_keys(),
+        }
+    }
+
+    /// Spawns a watchdog calling `on_stuck` once for every key held longer
+    /// than `threshold`, e.g. by a consumer which forgot to drop a `Msg`.
+    ///
+    /// The keys of the msgs are kept for the reports while a watchdog runs,
+    /// `StuckKey::msg_keys` is empty for the msgs delivered before it was
+    /// spawned. The watchdog stops when the returned handle is dropped.
+    #[must_use]
+    pub fn watchdog(
+        &self,
+        threshold: Duration,
+        on_stuck: impl FnMut(key_filter::StuckKey<K>) + Send + 'static,
+    ) -> watchdog::Watchdog
+    where
+        K: 'static,
+    {
+        watchdog::Watchdog::spawn(self.shared.filter.clone(), threshold, on_stuck)
+    }
+
+    /// Reports the `n` keys which made pending msgs skip the most, with how
+    /// long they were held and how many pending msgs collide with them now.
+    ///
+    /// Empty unless the channel was built `with_hot_keys`.
+    #[must_use]
+    pub fn hot_keys(&self, n: usize) -> Vec<key_filter::HotKey<K>> {
+        let pending: Vec<Vec<K>> = self.pending_keys().collect();
+        let mut hot_keys = self.shared.filter.hot_keys(n);
+        for hot_key in &mut hot_keys {
+            hot_key.queue_depth = pending
+                .iter()
+                .filter(|keys| key_filter::collides(keys, std::slice::from_ref(&hot_key.key)))
+                .count();
+        }
+        hot_keys
+    }
+
+    /// what a msg needs to release its keys.
+    fn release(&self) -> Release<K> {
+        Release {
+            shared: self.shared.clone(),
+            delivered_at: None,
+            #[cfg(feature = "tracing")]
+            span: tracing::Span::current(),
+            observer: self.observer.clone(),
+        }
+    }
+
+    /// walks the written slots from the head, delivered ones included.
+    fn nodes(&self) -> Nodes<'_, K, V> {
+        let entered = self.enter();
+        // loaded before the index, see `Position`. `SeqCst`, see `enter`.
+        let block = self.head.block.load(Ordering::SeqCst);
+        Nodes {
+            block,
+            index: self.head.index.load(Ordering::Acquire),
+            _entered: entered,
+        }
+    }
+
+    /// same as `nodes`, from the first slot still linked.
+    fn all_nodes(&self) -> Nodes<'_, K, V> {
+        let entered = self.enter();
+        let block = self.first.load(Ordering::SeqCst);
+        Nodes {
+            block,
+            index: unsafe { &*block }.start,
+            _entered: entered,
+        }
+    }
+
+    /// keeps the blocks from being recycled until the guard is dropped. a
+    /// thread enters the channel before loading a block, and stays in for a
+    /// short while only, see `Pinned` to hold a slot longer.
+    fn enter(&self) -> Entered<'_> {
+        // pairs with the store in `reclaim`, a thread can't enter an epoch
+        // the receiver bumped after unlinking blocks this thread may read.
+        let users = &self.users[self.epoch.load(Ordering::Acquire) & 1];
+        // pairs with the fence in `reclaim`: either the receiver sees this
+        // thread in, or this thread sees the blocks it unlinked. that takes
+        // a `SeqCst` increment here, and `SeqCst` loads of the block pointers
+        // once in, which cost no more than acquire loads, instead of a fence
+        // on every send.
+        users.fetch_add(1, Ordering::SeqCst);
+        Entered(users)
+    }
+
+    /// unlinks the blocks the head left behind, and the delivered ones past
+    /// it. they're recycled two epochs later, once the threads which entered
+    /// before are gone. called by the receivers, out of the channel.
+    fn reclaim(&self) {
+        // another receiver is at it.
+        let Ok(mut pool) = self.pool.try_lock() else {
+            return;
+        };
+        // only bumped under the pool lock.
+        let epoch = self.epoch.load(Ordering::Relaxed);
+        // the head may be on a block unlinked by a previous call, which the
+        // walk from `first` would never reach.
+        self.head.skip_unlinked();
+        let head = self.head.block.load(Ordering::Acquire);
+        let mut first = self.first.load(Ordering::Relaxed);
+        if first != head {
+            // a lagging tail would lead the senders into the unlinked blocks.
+            self.tail.catch_up(head);
+            self.first.store(head, Ordering::Release);
+            while first != head {
+                let next = unsafe { &*first }.next();
+                pool.retire(first, epoch);
+                first = next;
+            }
+        }
+        self.unlink_delivered(&mut pool, head, epoch);
+        if !pool.has_retired() {
+            return;
+        }
+        fence(Ordering::SeqCst);
+        // the threads which entered in the previous epoch are gone, so are
+        // those which may read the blocks retired before this epoch: they
+        // entered in the previous epoch or earlier, and the earlier ones
+        // were gone when the previous epoch began.
+        if self.users[(epoch + 1) & 1].load(Ordering::Acquire) == 0 {
+            pool.recycle(epoch);
+            self.epoch.store(epoch + 1, Ordering::Release);
+        }
+    }
+
+    /// unlinks the delivered blocks between `head` and the last block, so a
+    /// blocked msg doesn't hold back the blocks delivered after it.
+    fn unlink_delivered(
+        &self,
+        pool: &mut block::Pool<K, V>,
+        head: *mut block::Block<K, V>,
+        epoch: usize,
+    ) {
+        let mut prev = unsafe { &*head };
+        let mut unlinked = false;
+        // the receiver holds the pool lock, the blocks aren't recycled.
+        while let Some(block) = unsafe { prev.next().as_ref() } {
+            // the senders append to the last block.
+            if block.next().is_null() {
+                break;
+            }
+            if block.is_delivered() {
+                // a thread which moved a position onto the block before it was
+                // unlinked may leave it there until it leaves, so the threads
+                // which load the position may enter one epoch later.
+                pool.retire(prev.unlink_next(), epoch + 1);
+                unlinked = true;
+            } else {
+                prev = block;
+            }
+        }
+        if unlinked {
+            self.head.skip_unlinked();
+            self.tail.skip_unlinked();
+        }
+    }
+
+    /// moves the head past the delivered slots, so the scans skip them.
+    fn advance_head(&self) {
+        let entered = self.enter();
+        let from = self.head.block.load(Ordering::SeqCst);
+        let mut nodes = Nodes {
+            block: from,
+            index: self.head.index.load(Ordering::Acquire),
+            _entered: entered,
+        };
+        while nodes.peek().is_some_and(Node::is_delivered) {
+            nodes.index += 1;
+        }
+        self.head.index.fetch_max(nodes.index, Ordering::Release);
+        self.head.advance(from, nodes.block);
+    }
+
+    /// checks whether some msg is not delivered yet, scheduled ones included.
+    fn has_pending(&self) -> bool {
+        self.scheduled.load(Ordering::Acquire) > 0 || self.nodes().any(Node::is_pending)
+    }
+
+    /// checks whether some msg is not delivered yet and not scheduled later.
+    fn has_visible(&self) -> bool {
+        let now = Instant::now();
+        self.promote(now);
+        self.nodes()
+            .any(|node| node.is_pending() && node.is_visible(now))
+    }
+
+    fn send(&self, keys: Vec<K>, val: V) -> Result<(), SendError> {
+        self.append(keys, val, SendOpts::default()).map(|_| ())
+    }
+
+    fn send_with_priority(&self, keys: Vec<K>, val: V, priority: u8) -> Result<(), SendError> {
+        let opts = SendOpts {
+            priority,
+            ..SendOpts::default()
+        };
+        self.append(keys, val, opts).map(|_| ())
+    }
+
+    fn send_at(&self, keys: Vec<K>, val: V, at: Instant) -> Result<(), SendError> {
+        let opts = SendOpts {
+            not_before: Some(at),
+            ..SendOpts::default()
+        };
+        self.append(keys, val, opts).map(|_| ())
+    }
+
+    /// appends a msg, or folds it into a pending one on a folding channel.
+    /// fails if the channel is closed, or closed while full.
+    ///
+    /// a msg scheduled later is kept aside until it matures, there is no node
+    /// for it yet.
+    fn append(
+        &self,
+        keys: Vec<K>,
+        val: V,
+        opts: SendOpts,
+    ) -> Result<Option<&Node<K, V>>, SendError> {
+        #[cfg(feature = "tracing")]
+        let _span =
+            tracing::trace_span!(target: "mpsc", "send", priority = opts.priority).entered();
+        self.check_open()?;
+        self.wait_for_room(1)?;
+        let _entered = self.enter();
+        let seq = self.shared.counters.sent.fetch_add(1, Ordering::Relaxed);
+        self.observer.on_enqueue(&keys);
+        let val = match &self.fold {
+            Some(fold) => match self.coalesce(&keys, val, opts, fold) {
+                Ok(node) => {
+                    self.shared
+                        .counters
+                        .coalesced
+                        .fetch_add(1, Ordering::Relaxed);
+                    self.free_room(1);
+                    trace_event!(debug, keys = ?keys, "coalesce");
+                    return Ok(node);
+                }
+                Err(val) => val,
+            },
+            None => val,
+        };
+
+        trace_event!(debug, keys = ?keys, "send");
+        let now = Instant::now();
+        if let Some(at) = opts.not_before.filter(|&at| at > now) {
+            self.schedule(at, seq, keys, val, opts.priority, now);
+            return Ok(None);
+        }
+        Ok(Some(self.queue(keys, val, self.release(), opts, now)))
+    }
+
+    /// writes a msg in a new slot, the caller is in the channel.
+    fn queue(
+        &self,
+        keys: Vec<K>,
+        val: V,
+        release: Release<K>,
+        opts: SendOpts,
+        enqueued_at: Instant,
+    ) -> &Node<K, V> {
+        let (block, index) = self.claim(1);
+        let node = self.slot(block, index);
+        self.write(node, keys, val, release, opts, enqueued_at);
+        node
+    }
+
+    /// keeps a msg aside until `at`, `seq` orders the msgs maturing at once.
+    fn schedule(&self, at: Instant, seq: usize, keys: Vec<K>, val: V, priority: u8, now: Instant) {
+        let timer = Timer {
+            keys,
+            val,
+            // made by the sender, the msg keeps its span once promoted.
+            release: self.release(),
+            priority,
+            enqueued_at: now,
+        };
+        self.timers.lock().unwrap().insert((at, seq), timer);
+        self.scheduled.fetch_add(1, Ordering::Release);
+        // a receiver parked until a later maturity wakes up earlier.
+        self.shared.signal.notify();
+    }
+
+    /// queues the scheduled msgs which matured, in the order they mature.
+    /// the receivers call it before they scan the slots, with the instant
+    /// they check the visibility at.
+    fn promote(&self, now: Instant) {
+        if self.scheduled.load(Ordering::Acquire) == 0 {
+            return;
+        }
+        let _entered = self.enter();
+        let mut timers = self.timers.lock().unwrap();
+        while let Some(entry) = timers.first_entry() {
+            let (at, _) = *entry.key();
+            if at > now {
+                break;
+            }
+            let timer = entry.remove();
+            self.scheduled.fetch_sub(1, Ordering::Relaxed);
+            let opts = SendOpts {
+                priority: timer.priority,
+                not_before: Some(at),
+                ..SendOpts::default()
+            };
+            self.queue(
+                timer.keys,
+                timer.val,
+                timer.release,
+                opts,
+                timer.enqueued_at,
+            );
+        }
+    }
+
+    /// appends the msgs as one segment, the receiver sees all of them or none,
+    /// and no other msg is interleaved.
+    ///
+    /// msgs are never folded into pending ones here.
+    fn append_all(
+        &self,
+        msgs: impl IntoIterator<Item = (Vec<K>, V)>,
+    ) -> Result<Vec<&Node<K, V>>, SendError> {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::trace_span!(target: "mpsc", "send_all").entered();
+        self.check_open()?;
+        let msgs: Vec<_> = msgs.into_iter().collect();
+        self.wait_for_room(msgs.len())?;
+        self.shared
+            .counters
+            .sent
+            .fetch_add(msgs.len(), Ordering::Relaxed);
+        trace_event!(debug, msgs = msgs.len(), "send_all");
+        if msgs.is_empty() {
+            return Ok(vec![]);
+        }
+
+        for (keys, _) in &msgs {
+            self.observer.on_enqueue(keys);
+        }
+        let _entered = self.enter();
+        let (block, index) = self.claim(msgs.len());
+        let nodes: Vec<_> = (index..index + msgs.len())
+            .map(|index| self.slot(block, index))
+            .collect();
+        // the first slot is written last, it hides the others until then.
+        let now = Instant::now();
+        for (&node, (keys, val)) in nodes.iter().zip(msgs).rev() {
+            self.write(node, keys, val, self.release(), SendOpts::default(), now);
+        }
+        Ok(nodes)
+    }
+
+    /// a closed channel takes no more msgs, the receiver may have ended
+    /// its iteration already.
+    fn check_open(&self) -> Result<(), SendError> {
+        if self.is_closed() {
+            return Err(SendError);
+        }
+        Ok(())
+    }
+
+    /// reserves room for `n` msgs on a bounded channel, waits while it's
+    /// full, fails once it's closed, the receiver may be gone. more msgs
+    /// than the capacity wait for an empty channel.
+    fn wait_for_room(&self, n: usize) -> Result<(), SendError> {
+        let Some(capacity) = self.capacity else {
+            return Ok(());
+        };
+        let mut reserved = self.reserved.load(Ordering::Relaxed);
+        loop {
+            if reserved == 0 || reserved + n <= capacity {
+                // reserved at once, concurrent senders don't overshoot.
+                match self.reserved.compare_exchange_weak(
+                    reserved,
+                    reserved + n,
+                    Ordering::Relaxed,
+                    Ordering::Relaxed,
+                ) {
+                    Ok(_) => return Ok(()),
+                    Err(current) => reserved = current,
+                }
+                continue;
+            }
+            if self.is_closed() {
+                return Err(SendError);
+            }
+            self.wait();
+            reserved = self.reserved.load(Ordering::Relaxed);
+        }
+    }
+
+    /// gives back the room of `n` msgs, delivered or folded.
+    fn free_room(&self, n: usize) {
+        if self.capacity.is_some() {
+            self.reserved.fetch_sub(n, Ordering::Relaxed);
+        }
+    }
+
+    /// waits between two attempts, as configured by the wait strategy.
+    fn wait(&self) {
+        self.wait_strategy.wait();
+    }
+
+    /// retries `attempt` until it returns `Some`. the receiver is parked in
+    /// between until a msg is sent or released, the channel is closed, or
+    /// the next scheduled msg matures.
+    fn park_until_ready<T>(&self, mut attempt: impl FnMut() -> Option<T>) -> T {
+        if let Some(res) = attempt() {
+            return res;
+        }
+        let thread = sync::current();
+        loop {
+            // registered before the attempt, a change made after it unparks
+            // this thread.
+            self.shared.signal.register(&thread);
+            let res = attempt();
+            if res.is_none() {
+                if self.has_unwritten() {
+                    sync::yield_now();
+                } else {
+                    signal::park_until(self.next_maturity());
+                }
+            }
+            self.shared.signal.unregister(&thread);
+            if let Some(res) = res {
+                return res;
+            }
+        }
+    }
+
+    /// a sender claimed a slot it didn't write yet. it wakes up the receivers
+    /// registered before its claim only, so a receiver registered since then
+    /// yields until the slot is written instead of parking.
+    fn has_unwritten(&self) -> bool {
+        let mut nodes = self.nodes();
+        while nodes.next().is_some() {}
+        // pairs with the claim: either this receiver sees the slot claimed,
+        // or the sender sees the receiver registered.
+        nodes.index < self.tail.index.load(Ordering::SeqCst)
+    }
+
+    /// the earliest instant a pending msg scheduled later matures at.
+    fn next_maturity(&self) -> Option<Instant> {
+        if self.scheduled.load(Ordering::Acquire) == 0 {
+            return None;
+        }
+        let timers = self.timers.lock().unwrap();
+        timers.keys().next().map(|&(at, _)| at)
+    }
+
+    /// claims `n` consecutive slots with a single fetch-add, returns the
+    /// index of the first one and a block at or before it. the caller is in
+    /// the channel.
+    fn claim(&self, n: usize) -> (*mut block::Block<K, V>, usize) {
+        // loaded before the index, see `Position`.
+        let block = self.tail.block.load(Ordering::SeqCst);
+        // `SeqCst`, see `has_unwritten`.
+        let index = self.tail.index.fetch_add(n, Ordering::SeqCst);
+        (block, index)
+    }
+
+    /// the claimed slot `index`, searched from `from`. the tail moves to its
+    /// block, later senders start from there.
+    fn slot(&self, from: *mut block::Block<K, V>, index: usize) -> &Node<K, V> {
+        let block = block::Block::find(from, index, &self.pool);
+        self.tail.advance(from, block);
+        // the caller keeps the block from being recycled.
+        let block = unsafe { &*block };
+        &block.slots[index - block.start]
+    }
+
+    /// writes a msg in the claimed slot of `node`, the receiver sees it once
+    /// the earlier slots are written too.
+    fn write(
+        &self,
+        node: &Node<K, V>,
+        keys: Vec<K>,
+        val: V,
+        release: Release<K>,
+        opts: SendOpts,
+        enqueued_at: Instant,
+    ) {
+        let SendOpts {
+            priority,
+            not_before,
+            ..
+        } = opts;
+        if priority > 0 {
+            // published along with the node.
+            self.prioritized.store(true, Ordering::Relaxed);
+        }
+        let msg = Msg { keys, val, release };
+        let meta = Meta {
+            priority,
+            not_before,
+            enqueued_at,
+        };
+        // the slot was claimed by this sender only.
+        unsafe { node.write(msg, meta) };
+        self.shared.signal.notify_written();
+    }
+
+    /// folds `val` into the last pending msg colliding with `keys`, if that msg
+    /// has the same keys, becomes visible at the same time, and is no less
+    /// urgent than `opts` ask for. otherwise `val` is given back to be appended.
+    ///
+    /// the queued msgs are visible already, so a msg scheduled later is only
+    /// folded into one scheduled at the same instant, and a plain msg
+    /// colliding with no queued msg is appended.
+    fn coalesce(
+        &self,
+        keys: &[K],
+        val: V,
+        opts: SendOpts,
+        fold: &Fold<V>,
+    ) -> Result<Option<&Node<K, V>>, V> {
+        let last = self
+            .nodes()
+            .filter(|node| node.with_msg(|msg| key_filter::collides(&msg.keys, keys)) == Some(true))
+            .last();
+        let Some(node) = last.filter(|_| opts.not_before.is_none()) else {
+            if opts.rendezvous {
+                // the sender couldn't wait for the delivery.
+                return Err(val);
+            }
+            return self
+                .coalesce_scheduled(keys, val, opts, fold)
+                .map(|()| None);
+        };
+        // `None` orders first, the target matures no earlier than `val` may.
+        let meta = node.meta();
+        if meta.not_before < opts.not_before || meta.priority < opts.priority {
+            return Err(val);
+        }
+
+        // the msg may be delivered after the scan.
+        let mut val = Some(val);
+        node.with_msg_mut(|msg| {
+            if same_keys(&msg.keys, keys) {
+                fold(&mut msg.val, val.take().unwrap());
+            }
+        });
+        match val {
+            None => Ok(Some(node)),
+            Some(val) => Err(val),
+        }
+    }
+
+    /// same as `coalesce`, among the msgs scheduled later. the last one sent
+    /// colliding with `keys` is the target, `val` is never delayed by it nor
+    /// shown early.
+    fn coalesce_scheduled(
+        &self,
+        keys: &[K],
+        val: V,
+        opts: SendOpts,
+        fold: &Fold<V>,
+    ) -> Result<(), V> {
+        let mut timers = self.timers.lock().unwrap();
+        let last = timers
+            .iter_mut()
+            .filter(|(_, timer)| key_filter::collides(&timer.keys, keys))
+            .max_by_key(|((_, seq), _)| *seq);
+        let Some((&(at, _), timer)) = last else {
+            return Err(val);
+        };
+        // a plain msg is never folded into a scheduled one.
+        if Some(at) != opts.not_before
+            || timer.priority < opts.priority
+            || !same_keys(&timer.keys, keys)
+        {
+            return Err(val);
+        }
+        fold(&mut timer.val, val);
+        Ok(())
+    }
+
+    /// waits until the first msg is sent, returns `RecvError` if every
+    /// visible msg collides with the active keys, or none is pending.
+    ///
+    /// scheduled msgs don't count until they mature.
+    fn recv(&self) -> Result<Msg<K, V>, RecvError> {
+        self.recv_batch(1).map(|mut msgs| msgs.pop().unwrap())
+    }
+
+    /// same as `recv`, but delivers up to `max` msgs at once.
+    fn recv_batch(&self, max: usize) -> Result<Vec<Msg<K, V>>, RecvError> {
+        self.wait_while_empty()?;
+        self.try_recv_batch(max)
+    }
+
+    /// same as `recv`, but delivers the first deliverable msg together with
+    /// the pending msgs with the same keys.
+    fn recv_group(&self) -> Result<MsgGroup<K, V>, RecvError> {
+        self.wait_while_empty()?;
+        self.try_recv_group()
+    }
+
+    /// waits until the first msg is sent, returns `RecvError` once the
+    /// channel is drained after that, or closed and drained.
+    ///
+    /// a scheduled msg counts as sent once it matures, so the receiver waits
+    /// for it if nothing else is pending.
+    fn wait_while_empty(&self) -> Result<(), RecvError> {
+        self.park_until_ready(|| {
+            // loaded before the scans, msgs sent before `close` are seen by them.
+            let closed = self.is_closed();
+            if self.has_visible() {
+                return Some(Ok(()));
+            }
+            let sent = self.shared.counters.sent.load(Ordering::Relaxed) > 0;
+            ((closed || sent) && !self.has_pending()).then_some(Err(RecvError))
+        })
+    }
+
+    /// retries `try_recv` until it delivers, returns `RecvError` once the
+    /// channel is closed and drained.
+    fn block_on<T>(&self, try_recv: impl Fn() -> Result<T, RecvError>) -> Result<T, RecvError> {
+        self.park_until_ready(|| {
+            // loaded before the scan, msgs sent before `close` are seen by it.
+            let closed = self.is_closed();
+            match try_recv() {
+                Ok(res) => Some(Ok(res)),
+                Err(RecvError) => (closed && !self.has_pending()).then_some(Err(RecvError)),
+            }
+        })
+    }
+
+    /// same as `recv_batch`, but keeps accumulating deliverable msgs for up
+    /// to `linger` after the first ones are received.
+    fn recv_batch_timeout(
+        &self,
+        max: usize,
+        linger: Duration,
+    ) -> Result<Vec<Msg<K, V>>, RecvError> {
+        let msgs = self.recv_batch(max)?;
+        Ok(self.linger(msgs, max, linger))
+    }
+
+    /// tops `msgs` up to `max` with the msgs becoming deliverable within `linger`.
+    ///
+    /// the keys of `msgs` are active, so the msgs stay mutually non-colliding.
+    fn linger(&self, mut msgs: Vec<Msg<K, V>>, max: usize, linger: Duration) -> Vec<Msg<K, V>> {
+        let beginning = Instant::now();
+        while msgs.len() < max && beginning.elapsed() < linger {
+            let closed = self.is_closed();
+            match self.try_recv_batch(max - msgs.len()) {
+                Ok(more) => msgs.extend(more),
+                Err(_) if closed && !self.has_pending() => break,
+                Err(_) => self.wait(),
+            }
+        }
+        msgs
+    }
+
+    /// delivers the first deliverable msg and every later pending msg with
+    /// the same keys, unless an earlier pending msg with colliding keys is
+    /// between them. the keys are registered once for the whole group.
+    fn try_recv_group(&self) -> Result<MsgGroup<K, V>, RecvError> {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::trace_span!(target: "mpsc", "recv_group").entered();
+        let now = Instant::now();
+        self.promote(now);
+        let entered = self.enter();
+
+        // a snapshot, see `try_recv_batch`.
+        let active = self.shared.filter.active_keys();
+        let mut group: Vec<&Node<K, V>> = vec![];
+        let mut leader_keys: Vec<K> = vec![];
+        // keys of the pending msgs out of the group before the current one.
+        let mut earlier: Vec<K> = vec![];
+
+        for curr_node in self.nodes() {
+            if !curr_node.is_visible(now) {
+                continue;
+            }
+            let scanned = earlier.len();
+            // the keys are checked in place, only the leader's are cloned.
+            let verdict = curr_node.with_msg(|msg| {
+                let keys = &msg.keys;
+                let behind_earlier = key_filter::collides(&earlier, keys);
+                let (joins, blockers) = if group.is_empty() {
+                    let blockers = key_filter::blocking_keys(&active, keys);
+                    (blockers.is_empty() && !behind_earlier, blockers)
+                } else if same_keys(&leader_keys, keys) && !behind_earlier {
+                    (true, vec![])
+                } else {
+                    // the leader is about to be delivered, it blocks like an active msg.
+                    let blockers = key_filter::blocking_keys(&active, keys)
+                        .into_iter()
+                        .chain(colliding(&leader_keys, keys))
+                        .collect();
+                    (false, blockers)
+                };
+                if joins && group.is_empty() {
+                    leader_keys.clone_from(keys);
+                }
+                if !joins {
+                    earlier.extend_from_slice(keys);
+                }
+                (joins, blockers, behind_earlier)
+            });
+            let Some((joins, blockers, behind_earlier)) = verdict else {
+                continue;
+            };
+
+            if joins {
+                self.unblock(curr_node, now);
+                group.push(curr_node);
+                continue;
+            }
+            // a msg merely left out of the group is not blocked.
+            if group.is_empty() || !blockers.is_empty() || behind_earlier {
+                self.skip(curr_node, blockers, &earlier[..scanned], now);
+            }
+            // later msgs of the group would overtake this one.
+            if self.ordering == builder::OrderingMode::Strict {
+                break;
+            }
+        }
+
+        // collisions are detected, and no more avaliable msg can be read.
+        if group.is_empty() {
+            return Err(RecvError);
+        }
+
+        let enqueued_at = group[0].meta().enqueued_at;
+        let mut keys = None;
+        // the group carries the span of its first msg.
+        #[cfg(feature = "tracing")]
+        let mut span = None;
+        let vals: Vec<V> = group
+            .into_iter()
+            .filter_map(|node| {
+                let msg = self.take(node, now)?;
+                #[cfg(feature = "tracing")]
+                span.get_or_insert_with(|| msg.release.span.clone());
+                let (msg_keys, val) = msg.into_inner();
+                keys.get_or_insert(msg_keys);
+                Some(val)
+            })
+            .collect();
+        // another receiver took the whole group since the scan.
+        let Some(keys) = keys else {
+            return Err(RecvError);
+        };
+        self.shared.filter.put(&keys, enqueued_at);
+
+        self.shared
+            .counters
+            .delivered
+            .fetch_add(vals.len(), Ordering::Relaxed);
+        self.free_room(vals.len());
+        self.advance_head();
+        drop(entered);
+        self.reclaim();
+
+        Ok(MsgGroup {
+            keys,
+            vals,
+            release: Release {
+                delivered_at: Some(now),
+                #[cfg(feature = "tracing")]
+                span: span.unwrap(),
+                ..self.release()
+            },
+        })
+    }
+
+    /// same as `recv`, but returns `RecvError` instead of spinning on an empty channel.
+    fn try_recv(&self) -> Result<Msg<K, V>, RecvError> {
+        self.try_recv_batch(1).map(|mut msgs| msgs.pop().unwrap())
+    }
+
+    /// delivers up to `max` deliverable msgs, the ones with the highest
+    /// priority, the first ones among equals.
+    ///
+    /// a msg is deliverable if it doesn't collide with the active keys nor
+    /// with an earlier pending msg, so the msgs of a batch never collide
+    /// with each other.
+    fn try_recv_batch(&self, max: usize) -> Result<Vec<Msg<K, V>>, RecvError> {
+        if max == 0 {
+            return Ok(vec![]);
+        }
+        #[cfg(feature = "tracing")]
+        let _span = tracing::trace_span!(target: "mpsc", "recv", max).entered();
+        let now = Instant::now();
+        self.promote(now);
+        // without priorities the first deliverable msgs win, no need to scan the rest.
+        let prioritized = self.prioritized.load(Ordering::Relaxed);
+        let entered = self.enter();
+
+        // the filter is locked once, not for every scanned msg. a key released
+        // during the scan only holds its msgs back until the next one.
+        let active = self.shared.filter.active_keys();
+        let mut chosen: Vec<&Node<K, V>> = vec![];
+        // keys of the pending msgs before the current one, a msg can't overtake
+        // them even if the filter releases the key during the scan.
+        let mut earlier: Vec<K> = vec![];
+
+        for curr_node in self.nodes() {
+            if !prioritized && chosen.len() >= max {
+                break;
+            }
+            // a scheduled msg is invisible until it matures, it doesn't block
+            // later msgs either.
+            if !curr_node.is_visible(now) {
+                continue;
+            }
+            let scanned = earlier.len();
+            // the keys are checked in place, no need to clone them.
+            let verdict = curr_node.with_msg(|msg| {
+                let blockers = key_filter::blocking_keys(&active, &msg.keys);
+                let deliverable = blockers.is_empty() && !key_filter::collides(&earlier, &msg.keys);
+                earlier.extend_from_slice(&msg.keys);
+                (deliverable, blockers)
+            });
+            let Some((deliverable, blockers)) = verdict else {
+                continue;
+            };
+
+            if deliverable {
+                self.unblock(curr_node, now);
+                chosen.push(curr_node);
+            } else {
+                self.skip(curr_node, blockers, &earlier[..scanned], now);
+                if self.ordering == builder::OrderingMode::Strict {
+                    break;
+                }
+            }
+        }
+
+        // collisions are detected, and no more avaliable msg can be read.
+        if chosen.is_empty() {
+            return Err(RecvError);
+        }
+        // stable, so the order is kept among equal priorities.
+        chosen.sort_by_key(|node| std::cmp::Reverse(node.meta().priority));
+        chosen.truncate(max);
+
+        let (msgs, enqueued_at): (Vec<_>, Vec<_>) = chosen
+            .into_iter()
+            .filter_map(|node| Some((self.take(node, now)?, node.meta().enqueued_at)))
+            .unzip();
+        // another receiver took them since the scan.
+        if msgs.is_empty() {
+            return Err(RecvError);
+        }
+
+        // the keys of the whole batch are registered at once.
+        self.shared.filter.put_all(
+            msgs.iter()
+                .zip(enqueued_at)
+                .map(|(msg, at)| (msg.keys.as_slice(), at)),
+        );
+        self.shared
+            .counters
+            .delivered
+            .fetch_add(msgs.len(), Ordering::Relaxed);
+        self.free_room(msgs.len());
+        self.advance_head();
+        drop(entered);
+        self.reclaim();
+
+        Ok(msgs)
+    }
+
+    /// a scan found the msg of `node` blocked by the active `blockers`, or by
+    /// the `earlier` pending keys.
+    fn skip(&self, node: &Node<K, V>, mut blockers: Vec<K>, earlier: &[K], now: Instant) {
+        // reported once while the msg stays blocked, not once per scan.
+        if !node.block(now) {
+            return;
+        }
+        self.shared
+            .counters
+            .blocked_msgs
+            .fetch_add(1, Ordering::Relaxed);
+        self.shared.filter.record_skips(&blockers);
+        // cloned once per blocking episode. another receiver may have taken
+        // the msg since the scan.
+        let Some(keys) = node.with_msg(|msg| msg.keys.clone()) else {
+            return;
+        };
+        trace_event!(trace, keys = ?keys, "skip");
+        if self.observer.is_some() {
+            blockers.extend(colliding(earlier, &keys));
+            self.observer.on_skip(&keys, &blockers);
+        }
+    }
+
+    /// a scan found the msg of `node` deliverable.
+    fn unblock(&self, node: &Node<K, V>, now: Instant) {
+        if node.unblock(now) {
+            self.shared
+                .counters
+                .blocked_msgs
+                .fetch_sub(1, Ordering::Relaxed);
+        }
+    }
+
+    /// takes the msg out of a deliverable node, the node keeps an empty slot,
+    /// and records how long the msg waited. `None` if another receiver took
+    /// it first.
+    fn take(&self, node: &Node<K, V>, now: Instant) -> Option<Msg<K, V>> {
+        let mut msg = node.take()?;
 
-/// Channel acts as a linked-list to hold the msg.
-pub struct Channel<K, V>
+        let blocked = node.blocked_for();
+        let meta = node.meta();
+        let visible_at = meta
+            .not_before
+            .map_or(meta.enqueued_at, |at| at.max(meta.enqueued_at));
+        let waited = now.saturating_duration_since(visible_at);
+        let queued = waited.saturating_sub(blocked);
+        self.shared.counters.blocked.record_n(blocked, 1);
+        self.shared.counters.queueing.record_n(queued, 1);
+        trace_event!(debug, keys = ?msg.keys, ?queued, ?blocked, "deliver");
+        self.observer.on_deliver(&msg.keys);
+        // links the receiver back to the sender.
+        #[cfg(feature = "tracing")]
+        tracing::Span::current().follows_from(&msg.release.span);
+
+        msg.release.delivered_at = Some(now);
+        Some(msg)
+    }
+
+    /// blocks until a msg can be delivered, returns `RecvError` once the
+    /// channel is closed and drained.
+    fn recv_sync(&self) -> Result<Msg<K, V>, RecvError> {
+        self.recv_batch_sync(1).map(|mut msgs| msgs.pop().unwrap())
+    }
+
+    /// same as `recv_sync`, but delivers up to `max` msgs at once.
+    fn recv_batch_sync(&self, max: usize) -> Result<Vec<Msg<K, V>>, RecvError> {
+        self.block_on(|| self.try_recv_batch(max))
+    }
+
+    /// same as `recv_group`, but blocks like `recv_sync`.
+    fn recv_group_sync(&self) -> Result<MsgGroup<K, V>, RecvError> {
+        self.block_on(|| self.try_recv_group())
+    }
+
+    /// same as `recv_batch_sync`, but keeps accumulating deliverable msgs for
+    /// up to `linger` after the first ones are received.
+    fn recv_batch_timeout_sync(
+        &self,
+        max: usize,
+        linger: Duration,
+    ) -> Result<Vec<Msg<K, V>>, RecvError> {
+        let msgs = self.recv_batch_sync(max)?;
+        Ok(self.linger(msgs, max, linger))
+    }
+
+    fn send_sync(&self, keys: Vec<K>, val: V, priority: u8) -> Result<(), SendError> {
+        let opts = SendOpts {
+            priority,
+            ..SendOpts::default()
+        };
+        self.append_sync(keys, val, opts)
+    }
+
+    /// same as `send_sync`, the msg is invisible to the receiver until `at`.
+    ///
+    /// the sender holds the msg until it matures, then sends it as usual.
+    fn send_at_sync(&self, keys: Vec<K>, val: V, at: Instant) -> Result<(), SendError> {
+        // woken up early by the msgs sent or released meanwhile, and by `close`.
+        while Instant::now() < at && !self.is_closed() {
+            self.shared.signal.wait(at, || self.is_closed());
+        }
+        let opts = SendOpts {
+            not_before: Some(at),
+            ..SendOpts::default()
+        };
+        self.append_sync(keys, val, opts)
+    }
+
+    /// appends a msg and waits for its delivery. the rendezvous timeout
+    /// starts once the msg is visible.
+    ///
+    /// the msg is never kept aside, nor folded into a msg kept aside.
+    fn append_sync(&self, keys: Vec<K>, val: V, opts: SendOpts) -> Result<(), SendError> {
+        let visible_at = opts.not_before.unwrap_or_else(Instant::now);
+        let opts = SendOpts {
+            rendezvous: true,
+            ..opts
+        };
+        // the slot is read until the msg is delivered.
+        let pinned = {
+            let _entered = self.enter();
+            Pinned::new(self.append(keys, val, opts)?.into_iter().collect())
+        };
+        self.wait_delivered(&pinned.0, visible_at)
+    }
+
+    fn send_all_sync(&self, msgs: impl IntoIterator<Item = (Vec<K>, V)>) -> Result<(), SendError> {
+        let pinned = {
+            let _entered = self.enter();
+            Pinned::new(self.append_all(msgs)?)
+        };
+        self.wait_delivered(&pinned.0, Instant::now())
+    }
+
+    /// spins until every node is delivered, or the rendezvous started at
+    /// `beginning_park` times out, or the receiver is gone.
+    fn wait_delivered(
+        &self,
+        nodes: &[&Node<K, V>],
+        beginning_park: Instant,
+    ) -> Result<(), SendError> {
+        let time_out = self.rendezvous_timeout;
+
+        for node in nodes {
+            while !node.is_delivered() {
+                if beginning_park.elapsed() > time_out || self.disconnected.load(Ordering::Acquire)
+                {
+                    self.expire(nodes);
+                    return Err(SendError);
+                }
+                self.wait();
+            }
+        }
+        Ok(())
+    }
+
+    /// reports the msgs of `nodes` which are still pending to the observer.
+    fn expire(&self, nodes: &[&Node<K, V>]) {
+        for node in nodes {
+            if let Some(keys) = node.with_msg(|msg| msg.keys.clone()) {
+                self.observer.on_expire(&keys);
+            }
+        }
+    }
+}
+
+impl<K, V> Channel<K, V>
 where
-    K: Clone + HyperKey,
+    K: HyperKey + Clone,
 {
-    /// AtomicPtr impls Send + Sync, so Channel is Send + Sync by default.
-    /// head as a start point, no one could delete it.
-    head: AtomicPtr<Node<K, V>>,
-    tail: AtomicPtr<Node<K, V>>,
-    filter: key_filter::Filter<K>,
+    /// a sender is made.
+    fn add_sender(&self) {
+        self.senders.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// a sender is dropped, the last one closes the channel.
+    fn drop_sender(&self) {
+        if self.senders.fetch_sub(1, Ordering::AcqRel) == 1 {
+            self.closed.store(true, Ordering::Release);
+            self.shared.signal.notify();
+        }
+    }
 }
 
-impl<K, V> Default for Channel<K, V>
+impl<K, V> Debug for Channel<K, V>
 where
     K: HyperKey + Send + Debug + Clone,
     V: Send + Debug,
 {
-    fn default() -> Self {
-        let node = Node::default();
-        let node_ptr = Box::leak(Box::new(node));
-        Self {
-            head: AtomicPtr::new(node_ptr),
-            tail: AtomicPtr::new(node_ptr),
-            filter: key_filter::Filter::default(),
-        }
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Channel")
+            .field("name", &self.name)
+            .field("closed", &self.is_closed())
+            .field("pending", &self.pending_len())
+            .field("active_keys", &self.active_keys())
+            .finish_non_exhaustive()
     }
 }
 
-impl<K, V> Channel<K, V>
+/// Blocking iterator over the msgs of a `Channel`, ends once the channel is
+/// closed, or its senders are dropped, and drained.
+pub struct Iter<'a, K, V>
+where
+    K: HyperKey + Clone,
+{
+    chan: &'a Channel<K, V>,
+}
+
+impl<K, V> Iterator for Iter<'_, K, V>
 where
     K: HyperKey + Send + Debug + Clone,
     V: Send + Debug,
 {
-    /// Just like create a Linked-List.
-    /// Init state:
-    /// # Channel head ---    tail ---
-    /// #                |           |
-    /// #                |           |
-    /// #               Node <--------    
-    ///
-    ///
-    /// # Channel head ---    tail -----------
-    /// #                |                   |
-    /// #                |                   |
-    /// #               Node ---> Node ---> `null_ptr`
-    ///
-    #[must_use]
-    pub fn new() -> Channel<K, V> {
-        let node = Node::default();
-        let node_ptr = Box::leak(Box::new(node));
-        Self {
-            head: AtomicPtr::new(node_ptr),
-            tail: AtomicPtr::new(node_ptr),
-            filter: key_filter::Filter::default(),
-        }
+    type Item = Msg<K, V>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.chan.recv_sync().ok()
     }
+}
 
-    /// try to occupy the tail node.
-    fn send(&self, keys: Vec<K>, val: V) {
-        // try to append a new node to the Channel.
-        // todo: optimize the channel appending.
-        let new_node = Node {
-            next: AtomicPtr::new(ptr::null_mut()),
-            data: Box::into_raw(Box::new(Some(Msg {
-                keys,
-                val,
-                filter: self.filter.clone(),
-            }))),
-            is_destroy: AtomicBool::new(false),
-            is_hold: AtomicBool::new(false),
-        };
-        let new_node = Box::into_raw(Box::new(new_node));
+/// Iterator over the msgs which are deliverable right now.
+pub struct TryIter<'a, K, V>
+where
+    K: HyperKey + Clone,
+{
+    chan: &'a Channel<K, V>,
+}
+
+impl<K, V> Iterator for TryIter<'_, K, V>
+where
+    K: HyperKey + Send + Debug + Clone,
+    V: Send + Debug,
+{
+    type Item = Msg<K, V>;
 
-        let mut tail = unsafe { &*(self.tail.load(Ordering::SeqCst)) };
+    fn next(&mut self) -> Option<Self::Item> {
+        self.chan.try_recv().ok()
+    }
+}
 
-        let expected: *mut Node<K, V> = ptr::null_mut();
+/// How a msg is appended to the `Channel`.
+#[derive(Debug, Default, Clone, Copy)]
+struct SendOpts {
+    priority: u8,
+    not_before: Option<Instant>,
+    /// the sender waits for the delivery of the msg.
+    rendezvous: bool,
+}
 
-        loop {
-            // can not depend on the tail, so AcqRel.
-            match tail.next.compare_exchange_weak(
-                expected,
-                new_node,
-                Ordering::SeqCst,
-                Ordering::Relaxed,
-            ) {
-                // append successully.
-                Ok(_) => break,
-                // curr is not null.
-                Err(curr) => tail = unsafe { &*curr },
-            }
-        }
+/// A msg scheduled later, kept out of the slots until it matures.
+struct Timer<K, V>
+where
+    K: HyperKey + Clone,
+{
+    keys: Vec<K>,
+    val: V,
+    release: Release<K>,
+    priority: u8,
+    enqueued_at: Instant,
+}
+
+/// the slot is not written yet.
+const EMPTY: u8 = 0;
+/// the msg is waiting for the receiver.
+const PENDING: u8 = 1;
+/// the msg is borrowed, or being moved out, by one thread.
+const BUSY: u8 = 2;
+/// the msg was moved out to the receiver.
+const DELIVERED: u8 = 3;
+
+/// Written along with the msg, and kept once it's delivered.
+#[derive(Debug, Clone, Copy)]
+struct Meta {
+    /// higher priority msgs are delivered first, as long as they don't
+    /// overtake an earlier msg with colliding keys.
+    priority: u8,
 
-        self.tail.store(new_node, Ordering::SeqCst);
+    /// the msg can't be delivered before this instant.
+    not_before: Option<Instant>,
+
+    /// when the msg was sent.
+    enqueued_at: Instant,
+}
+
+/// A slot of a `Block`.
+#[derive(Debug)]
+struct Node<K, V>
+where
+    K: HyperKey + Clone,
+{
+    /// The msg to be shared, initialized while the state is `PENDING` or `BUSY`.
+    data: UnsafeCell<MaybeUninit<Msg<K, V>>>,
+
+    /// initialized unless the state is `EMPTY`.
+    meta: UnsafeCell<MaybeUninit<Meta>>,
+
+    /// one of `EMPTY`, `PENDING`, `BUSY` and `DELIVERED`.
+    state: AtomicU8,
+
+    /// when the ongoing block began, as seen by the scans, and how long the
+    /// msg was blocked before. only the receiver touches it.
+    blocked: Mutex<(Option<Instant>, Duration)>,
+
+    /// sync senders waiting for the delivery of the msg, see `Pinned`.
+    pins: AtomicUsize,
+}
+
+impl<K, V> Node<K, V>
+where
+    K: HyperKey + Clone,
+{
+    /// writes an `EMPTY` node at `node`, the msg is left uninitialized in
+    /// place instead of being moved there.
+    ///
+    /// # Safety
+    /// `node` is valid for writes, and holds no node yet.
+    unsafe fn init(node: *mut Self) {
+        unsafe {
+            ptr::addr_of_mut!((*node).state).write(AtomicU8::new(EMPTY));
+            ptr::addr_of_mut!((*node).blocked).write(Mutex::default());
+            ptr::addr_of_mut!((*node).pins).write(AtomicUsize::new(0));
+        }
     }
 
-    fn recv(&self) -> Result<Msg<K, V>, RecvError> {
-        let head = unsafe {
-            // only one thread modifies the head, so it's not important to choose a right ordering.
-            &*self.head.load(Ordering::SeqCst)
-        };
+    /// empties a `DELIVERED` node no thread reads anymore.
+    fn reset(&mut self) {
+        debug_assert!(self.is_delivered());
+        self.state = AtomicU8::new(EMPTY);
+        self.blocked = Mutex::default();
+        self.pins = AtomicUsize::new(0);
+    }
 
-        while head.next.load(Ordering::SeqCst).is_null() {
-            // spin
+    /// writes the msg of an `EMPTY` slot, `EMPTY` -> `PENDING`.
+    ///
+    /// # Safety
+    /// the caller claimed the slot, no other thread writes it.
+    unsafe fn write(&self, msg: Msg<K, V>, meta: Meta) {
+        unsafe {
+            (*self.data.get()).write(msg);
+            (*self.meta.get()).write(meta);
         }
+        self.state.store(PENDING, Ordering::Release);
+    }
 
-        let mut curr_node = unsafe { &*head.next.load(Ordering::SeqCst) };
+    fn is_written(&self) -> bool {
+        self.state.load(Ordering::Acquire) != EMPTY
+    }
 
-        loop {
-            if curr_node.is_destroy.load(Ordering::SeqCst) {
-                // try to destroy the node.
-                // 1. check next, if next is null, return to the head.next
-                // 2. next is not null, curr becomes curr.next
-                if curr_node.next.load(Ordering::SeqCst).is_null() {
-                    return Err(RecvError);
-                }
-                curr_node = unsafe { &*curr_node.next.load(Ordering::SeqCst) };
-                continue;
-            }
+    /// the slot must be written.
+    fn meta(&self) -> &Meta {
+        debug_assert!(self.is_written());
+        // written before the state left `EMPTY`, and never changed after.
+        unsafe { (*self.meta.get()).assume_init_ref() }
+    }
 
-            let msg_opt = unsafe { &*curr_node.data };
-
-            match msg_opt {
-                Some(_) => {
-                    // todo: more functional format.
-                    //
-                    // let msg = unsafe {
-                    // take the value out.
-                    //    Box::from_raw(msg_node.data.get()).unwrap()
-
-                    let msg = unsafe { Box::from_raw(curr_node.data).unwrap() };
-
-                    if self.filter.contains(&msg.keys) {
-                        Box::into_raw(Box::new(msg));
-                        if curr_node.next.load(Ordering::SeqCst).is_null() {
-                            // collisions are detected, and no more avaliable msg can be read.
-                            return Err(RecvError);
-                        }
-                        curr_node = unsafe { &*curr_node.next.load(Ordering::SeqCst) };
-                        // avoids double free.
-                        continue;
-                    }
-
-                    curr_node.is_destroy.store(true, Ordering::SeqCst);
-                    self.filter.put(&msg.keys);
-
-                    return Ok(msg);
-                }
-                None => return Err(RecvError {}),
-            }
-        }
+    fn is_visible(&self, now: Instant) -> bool {
+        self.meta().not_before.is_none_or(|at| at <= now)
     }
 
-    fn recv_sync(&self) -> Result<Msg<K, V>, RecvError> {
-        self.recv()
+    /// called by a scan finding the msg blocked, returns whether the msg was
+    /// deliverable until now.
+    fn block(&self, now: Instant) -> bool {
+        let mut blocked = self.blocked.lock().unwrap();
+        let starts = blocked.0.is_none();
+        blocked.0.get_or_insert(now);
+        starts
     }
 
-    fn send_sync(&self, keys: Vec<K>, val: V) -> Result<(), SendError> {
-        // naive implementation.
-        let new_node = Node {
-            next: AtomicPtr::new(ptr::null_mut()),
-            data: Box::into_raw(Box::new(Some(Msg {
-                keys,
-                val,
-                filter: self.filter.clone(),
-            }))),
-            is_destroy: AtomicBool::new(false),
-            is_hold: AtomicBool::new(false),
+    /// called by a scan finding the msg deliverable, ends the ongoing block.
+    /// returns whether there was one.
+    fn unblock(&self, now: Instant) -> bool {
+        let mut blocked = self.blocked.lock().unwrap();
+        let Some(since) = blocked.0.take() else {
+            return false;
         };
-        let new_node = Box::into_raw(Box::new(new_node));
+        blocked.1 += now.saturating_duration_since(since);
+        true
+    }
+
+    /// total time the msg was blocked, the delivering scan unblocks it first.
+    fn blocked_for(&self) -> Duration {
+        self.blocked.lock().unwrap().1
+    }
+
+    /// a sync sender still reads the slot.
+    pub(crate) fn is_pinned(&self) -> bool {
+        // pairs with the release in `Pinned::drop`.
+        self.pins.load(Ordering::Acquire) > 0
+    }
 
-        let mut tail = unsafe { &*(self.tail.load(Ordering::SeqCst)) };
+    /// the msg is not delivered yet.
+    fn is_pending(&self) -> bool {
+        matches!(self.state.load(Ordering::Acquire), PENDING | BUSY)
+    }
 
-        let expected: *mut Node<K, V> = ptr::null_mut();
+    fn is_delivered(&self) -> bool {
+        self.state.load(Ordering::Acquire) == DELIVERED
+    }
 
+    /// `PENDING` -> `BUSY`, spins while another thread borrows the msg.
+    /// returns `false` if the node holds no msg.
+    fn lock(&self) -> bool {
         loop {
-            // can not depend on the tail, so AcqRel.
-            match tail.next.compare_exchange_weak(
-                expected,
-                new_node,
-                Ordering::SeqCst,
+            match self.state.compare_exchange_weak(
+                PENDING,
+                BUSY,
+                Ordering::Acquire,
                 Ordering::Relaxed,
             ) {
-                // append successully.
-                Ok(_) => break,
-                // curr is not null.
-                Err(curr) => tail = unsafe { &*curr },
+                Ok(_) => return true,
+                // `PENDING` is a spurious failure.
+                Err(BUSY | PENDING) => sync::spin_loop(),
+                Err(_) => return false,
             }
         }
+    }
 
-        self.tail.store(new_node, Ordering::SeqCst);
+    /// `BUSY` -> `PENDING`.
+    fn unlock(&self) {
+        self.state.store(PENDING, Ordering::Release);
+    }
 
-        let stored_node = unsafe { &*new_node };
-        let beginning_park = Instant::now();
-        let time_out = Duration::from_secs(TIME_OUT);
+    /// runs `f` on the msg if it's still pending, locking the node so the msg
+    /// isn't taken out meanwhile.
+    fn with_msg<R>(&self, f: impl FnOnce(&Msg<K, V>) -> R) -> Option<R> {
+        if !self.lock() {
+            return None;
+        }
+        // initialized while `BUSY`, and borrowed by this thread only.
+        let res = f(unsafe { (*self.data.get()).assume_init_ref() });
+        self.unlock();
+        Some(res)
+    }
 
-        while !stored_node.is_destroy.load(Ordering::SeqCst) {
-            let elapsed = beginning_park.elapsed();
-            if time_out - elapsed < Duration::from_secs(0) {
-                return Err(SendError);
-            }
-            std::hint::spin_loop();
+    /// same as `with_msg`, `f` may modify the msg.
+    fn with_msg_mut<R>(&self, f: impl FnOnce(&mut Msg<K, V>) -> R) -> Option<R> {
+        if !self.lock() {
+            return None;
         }
-        // todo: verify the correctness.
-        // difference bettween sync and async version:
-        // sync: sender's duty to drop the node.
-        // async: receiver's duty to drop the node.
-        //
-        // let head = unsafe { &*self.head.load(Ordering::Relaxed) };
-        // let drop = unsafe { Box::from_raw(new_node) };
+        // initialized while `BUSY`, and borrowed by this thread only.
+        let res = f(unsafe { (*self.data.get()).assume_init_mut() });
+        self.unlock();
+        Some(res)
+    }
 
-        // head.next.store(drop.next.load(Ordering::Acquire), Ordering::Release);
-        Ok(())
+    /// moves the msg out, `PENDING` -> `DELIVERED`. `None` if it's already
+    /// delivered.
+    fn take(&self) -> Option<Msg<K, V>> {
+        if !self.lock() {
+            return None;
+        }
+        // initialized while `BUSY`, and never read again once `DELIVERED`.
+        let msg = unsafe { (*self.data.get()).assume_init_read() };
+        self.state.store(DELIVERED, Ordering::Release);
+        Some(msg)
     }
 }
 
-#[derive(Debug)]
-struct Node<K, V>
+impl<K, V> Drop for Node<K, V>
 where
     K: HyperKey + Clone,
 {
-    /// Node needs to be shared across the thread boundary, so the AtomicOpt is nessessary.
-    next: AtomicPtr<Node<K, V>>,
+    fn drop(&mut self) {
+        if self.is_pending() {
+            // initialized, and never delivered so its keys were never acquired.
+            let msg = unsafe { self.data.get_mut().assume_init_read() };
+            drop(msg.into_inner());
+        }
+    }
+}
 
-    /// The msg to be shared.
-    data: *mut Option<Msg<K, V>>,
+/// Iterator over the written slots of a `Channel`, in the order they were
+/// claimed.
+struct Nodes<'a, K, V>
+where
+    K: HyperKey + Clone,
+{
+    /// the block of `index`, or an earlier one. kept raw, it may end up in
+    /// the head, and the pool writes through it once the block is recycled.
+    block: *mut block::Block<K, V>,
+    index: usize,
+    _entered: Entered<'a>,
+}
 
-    /// like a lock to occupy the location.
-    /// after droping the msg, we can destroy this given node.
-    is_destroy: AtomicBool,
+/// A thread in a `Channel`, see `Channel::enter`.
+struct Entered<'a>(&'a AtomicUsize);
 
-    is_hold: AtomicBool,
+impl Drop for Entered<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::Release);
+    }
 }
 
-impl<K, V> Default for Node<K, V>
+/// Slots read by a sync sender out of the channel, their blocks are not
+/// recycled until the guard is dropped.
+struct Pinned<'a, K, V>(Vec<&'a Node<K, V>>)
 where
-    K: Clone + HyperKey,
+    K: HyperKey + Clone;
+
+impl<'a, K, V> Pinned<'a, K, V>
+where
+    K: HyperKey + Clone,
 {
-    fn default() -> Self {
-        Self {
-            next: AtomicPtr::new(ptr::null_mut()),
-            data: Box::into_raw(Box::new(None)),
-            is_destroy: AtomicBool::new(false),
-            is_hold: AtomicBool::new(false),
+    /// pins `nodes`, the caller is in the channel and may leave it once they
+    /// are pinned.
+    fn new(nodes: Vec<&'a Node<K, V>>) -> Self {
+        for node in &nodes {
+            // seen by the receiver which sees this thread leave.
+            node.pins.fetch_add(1, Ordering::Relaxed);
+        }
+        Self(nodes)
+    }
+}
+
+impl<K, V> Drop for Pinned<'_, K, V>
+where
+    K: HyperKey + Clone,
+{
+    fn drop(&mut self) {
+        for node in &self.0 {
+            node.pins.fetch_sub(1, Ordering::Release);
+        }
+    }
+}
+
+impl<'a, K, V> Nodes<'a, K, V>
+where
+    K: HyperKey + Clone + 'a,
+    V: 'a,
+{
+    /// the slot at `index` if it's written.
+    fn peek(&mut self) -> Option<&'a Node<K, V>> {
+        // the iterator is in the channel, the blocks aren't recycled.
+        let mut block: &'a block::Block<K, V> = unsafe { &*self.block };
+        while self.index >= block.start + block::BLOCK_CAP {
+            let next = block.next();
+            block = unsafe { next.as_ref() }?;
+            self.block = next;
         }
+        // the slots of the blocks unlinked out of order are delivered.
+        self.index = self.index.max(block.start);
+        let node = &block.slots[self.index - block.start];
+        // a slot not written yet hides the later ones, so a sender's msgs
+        // show up in order, and a segment all at once.
+        node.is_written().then_some(node)
+    }
+}
+
+impl<'a, K, V> Iterator for Nodes<'a, K, V>
+where
+    K: HyperKey + Clone + 'a,
+    V: 'a,
+{
+    type Item = &'a Node<K, V>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.peek()?;
+        self.index += 1;
+        Some(node)
     }
 }
 
+/// the keys of `a` colliding with a key of `b`.
+fn colliding<'a, K: HyperKey + Clone>(a: &'a [K], b: &'a [K]) -> impl Iterator<Item = K> + 'a {
+    a.iter()
+        .filter(|key| b.iter().any(|elem| key.collision_detect(elem)))
+        .cloned()
+}
+
+/// checks whether every key of `a` collides with a key of `b` and the other way round.
+fn same_keys<K: HyperKey>(a: &[K], b: &[K]) -> bool {
+    a.iter()
+        .all(|key| b.iter().any(|elem| key.collision_detect(elem)))
+        && b.iter()
+            .all(|key| a.iter().any(|elem| key.collision_detect(elem)))
+}
+
 pub trait HyperKey<OtherKey = Self> {
     // todo: add macro support.
     fn collision_detect(&self, other: &OtherKey) -> bool;
@@ -276,8 +1865,101 @@ where
 {
     pub keys: Vec<K>,
     pub val: T,
-    // true is active, else false.
-    filter: key_filter::Filter<K>,
+    release: Release<K>,
+}
+
+impl<K, V> Msg<K, V>
+where
+    K: HyperKey + Clone,
+{
+    /// The `send` span of the msg, a child of the span current in the
+    /// sender. A processing span can follow from it to link back to the
+    /// sender.
+    #[cfg(feature = "tracing")]
+    #[must_use]
+    pub fn span(&self) -> &tracing::Span {
+        &self.release.span
+    }
+
+    /// split the msg into its keys and value, the keys are not released.
+    fn into_inner(self) -> (Vec<K>, V) {
+        let msg = ManuallyDrop::new(self);
+        // every field is read exactly once and `msg` itself is never dropped.
+        unsafe {
+            drop(ptr::read(&raw const msg.release));
+            (
+                ptr::read(&raw const msg.keys),
+                ptr::read(&raw const msg.val),
+            )
+        }
+    }
+
+    /// split the msg into its value and a guard which still holds the keys.
+    pub(crate) fn into_parts(self) -> (KeyGuard<K>, V) {
+        let msg = ManuallyDrop::new(self);
+        // every field is read exactly once and `msg` itself is never dropped.
+        unsafe {
+            (
+                KeyGuard {
+                    keys: ptr::read(&raw const msg.keys),
+                    release: ptr::read(&raw const msg.release),
+                },
+                ptr::read(&raw const msg.val),
+            )
+        }
+    }
+}
+
+/// Msgs with the same keys delivered at once, the keys stay active until the
+/// group is dropped.
+#[derive(Debug)]
+pub struct MsgGroup<K, T>
+where
+    K: HyperKey + Clone,
+{
+    pub keys: Vec<K>,
+    /// in the order they were sent.
+    pub vals: Vec<T>,
+    release: Release<K>,
+}
+
+#[cfg(feature = "tracing")]
+impl<K, V> MsgGroup<K, V>
+where
+    K: HyperKey + Clone,
+{
+    /// The `send` span of the first msg of the group.
+    #[must_use]
+    pub fn span(&self) -> &tracing::Span {
+        &self.release.span
+    }
+}
+
+impl<K, V> Drop for MsgGroup<K, V>
+where
+    K: HyperKey + Clone,
+{
+    fn drop(&mut self) {
+        self.release.release(&self.keys, self.vals.len());
+    }
+}
+
+/// The keys of a delivered msg, they stay active until the guard is dropped.
+pub(crate) struct KeyGuard<K>
+where
+    K: HyperKey + Clone,
+{
+    keys: Vec<K>,
+    release: Release<K>,
+}
+
+impl<K> Drop for KeyGuard<K>
+where
+    K: HyperKey + Clone,
+{
+    fn drop(&mut self) {
+        self.release.release(&self.keys, 1);
+    }
 }
 
 impl<K, V> Drop for Msg<K, V>
@@ -285,22 +1967,68 @@ where
     K: HyperKey + Clone,
 {
     fn drop(&mut self) {
-        self.filter.pop(&self.keys);
+        self.release.release(&self.keys, 1);
+    }
+}
+
+/// The part of a `Channel` its msgs hold on to, cloned once per msg.
+#[derive(Debug)]
+struct Shared<K>
+where
+    K: HyperKey + Clone,
+{
+    filter: key_filter::Filter<K>,
+    counters: stats::Counters,
+    /// wakes up the parked receivers.
+    signal: signal::Signal,
+}
+
+/// Shared with the channel, releases the keys of delivered msgs.
+#[derive(Debug)]
+struct Release<K>
+where
+    K: HyperKey + Clone,
+{
+    shared: Arc<Shared<K>>,
+    /// set once the msg is delivered, the hold time starts.
+    delivered_at: Option<Instant>,
+    /// the span of the sender.
+    #[cfg(feature = "tracing")]
+    span: tracing::Span,
+    observer: observer::Observer<K>,
+}
+
+impl<K> Release<K>
+where
+    K: HyperKey + Clone,
+{
+    fn release(&self, keys: &[K], msgs: usize) {
+        self.shared.filter.pop(keys);
+        self.shared
+            .counters
+            .released
+            .fetch_add(msgs, Ordering::Relaxed);
+        let held_for = self.delivered_at.map(|at| at.elapsed());
+        if let Some(held_for) = held_for {
+            self.shared.counters.hold.record_n(held_for, msgs);
+        }
+        trace_event!(debug, msgs, ?held_for, "release");
+        self.observer.on_release(keys);
+        self.shared.signal.notify();
     }
 }
 
-// todo: impl Drop for Channel to release memory.
 impl<K, V> Drop for Channel<K, V>
 where
     K: HyperKey + Clone,
 {
-    /// This drop is dangerous.
+    /// frees every linked block, the undelivered msgs are dropped with them.
+    /// the pool frees the others.
     fn drop(&mut self) {
-        let head = unsafe { Box::from_raw(self.head.load(Ordering::Relaxed)) };
-        let mut next = head.next.load(Ordering::SeqCst);
+        let mut next = self.first.load(Ordering::Relaxed);
         while !next.is_null() {
             let drop = unsafe { Box::from_raw(next) };
-            next = drop.next.load(Ordering::SeqCst);
+            next = drop.next();
         }
     }
 }
@@ -0,0 +1,103 @@
+use std::fmt;
+use std::sync::Arc;
+
+/// Hooks into the lifecycle of the msgs of a `Channel`, registered with
+/// `Channel::with_observer`.
+///
+/// Callbacks run on the thread doing the operation, while the channel is
+/// working, so they should be quick. Every callback does nothing by default.
+pub trait ChannelObserver<K>: Send + Sync {
+    /// a msg is sent, folded ones included.
+    fn on_enqueue(&self, keys: &[K]) {
+        let _ = keys;
+    }
+
+    /// a pending msg is skipped by the receiver, `blocking_keys` are the
+    /// active keys and the keys of earlier pending msgs it collides with.
+    /// called once until the msg is deliverable again, however many scans
+    /// skip it meanwhile.
+    fn on_skip(&self, keys: &[K], blocking_keys: &[K]) {
+        let _ = (keys, blocking_keys);
+    }
+
+    /// a msg is handed out by the receiver, its keys become active.
+    fn on_deliver(&self, keys: &[K]) {
+        let _ = keys;
+    }
+
+    /// a delivered msg, or group of msgs, is dropped and its keys are released.
+    fn on_release(&self, keys: &[K]) {
+        let _ = keys;
+    }
+
+    /// a sync sender gave up waiting for the delivery of its msg, the msg
+    /// stays pending.
+    fn on_expire(&self, keys: &[K]) {
+        let _ = keys;
+    }
+}
+
+/// The observer of a channel, if any, shared with its msgs.
+pub(crate) struct Observer<K>(Option<Arc<dyn ChannelObserver<K>>>);
+
+impl<K> Observer<K> {
+    pub(crate) fn new(observer: Arc<dyn ChannelObserver<K>>) -> Self {
+        Self(Some(observer))
+    }
+
+    pub(crate) fn is_some(&self) -> bool {
+        self.0.is_some()
+    }
+
+    pub(crate) fn on_enqueue(&self, keys: &[K]) {
+        if let Some(observer) = &self.0 {
+            observer.on_enqueue(keys);
+        }
+    }
+
+    pub(crate) fn on_skip(&self, keys: &[K], blocking_keys: &[K]) {
+        if let Some(observer) = &self.0 {
+            observer.on_skip(keys, blocking_keys);
+        }
+    }
+
+    pub(crate) fn on_deliver(&self, keys: &[K]) {
+        if let Some(observer) = &self.0 {
+            observer.on_deliver(keys);
+        }
+    }
+
+    pub(crate) fn on_release(&self, keys: &[K]) {
+        if let Some(observer) = &self.0 {
+            observer.on_release(keys);
+        }
+    }
+
+    pub(crate) fn on_expire(&self, keys: &[K]) {
+        if let Some(observer) = &self.0 {
+            observer.on_expire(keys);
+        }
+    }
+}
+
+impl<K> Default for Observer<K> {
+    fn default() -> Self {
+        Self(None)
+    }
+}
+
+impl<K> Clone for Observer<K> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+impl<K> fmt::Debug for Observer<K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(if self.is_some() {
+            "Observer(Some(..))"
+        } else {
+            "Observer(None)"
+        })
+    }
+}
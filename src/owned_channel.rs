@@ -0,0 +1,241 @@
+use crate::sync::{Arc, Ordering};
+use crate::{Channel, HyperKey, Iter, Msg, MsgGroup, RecvError, SendError, TryIter};
+use std::fmt::Debug;
+use std::time::{Duration, Instant};
+
+/// A sender owning a share of the channel, built by `ChannelBuilder::build`.
+/// The channel is closed when the last sender is dropped.
+pub struct Sender<K, V>
+where
+    K: HyperKey + Clone,
+{
+    pub(crate) chan: Arc<Channel<K, V>>,
+}
+
+/// The receiver owning a share of the channel, built by
+/// `ChannelBuilder::build`. The channel is closed when it is dropped, the
+/// senders waiting for room or for a delivery return `SendError`.
+pub struct Receiver<K, V>
+where
+    K: HyperKey + Clone,
+{
+    pub(crate) chan: Arc<Channel<K, V>>,
+}
+
+impl<K, V> Sender<K, V>
+where
+    K: HyperKey + Send + Debug + Clone,
+    V: Send + Debug,
+{
+    /// Sends a msg without waiting for its delivery, waits for room on a
+    /// bounded channel.
+    ///
+    /// # Errors
+    /// will return `SendError` if the channel is closed, or the receiver is
+    /// dropped while waiting for room.
+    pub fn send(&self, keys: Vec<K>, val: V) -> Result<(), SendError> {
+        self.chan.send(keys, val)
+    }
+
+    /// Same as `send`, the msg is delivered before lower priority msgs.
+    ///
+    /// # Errors
+    /// will return `SendError` if the channel is closed.
+    pub fn send_with_priority(&self, keys: Vec<K>, val: V, priority: u8) -> Result<(), SendError> {
+        self.chan.send_with_priority(keys, val, priority)
+    }
+
+    /// Same as `send`, the msg is invisible to the receiver until `at`.
+    ///
+    /// # Errors
+    /// will return `SendError` if the channel is closed.
+    pub fn send_at(&self, keys: Vec<K>, val: V, at: Instant) -> Result<(), SendError> {
+        self.chan.send_at(keys, val, at)
+    }
+
+    /// Same as `send_at`, `delay` from now.
+    ///
+    /// # Errors
+    /// will return `SendError` if the channel is closed.
+    pub fn send_after(&self, keys: Vec<K>, val: V, delay: Duration) -> Result<(), SendError> {
+        self.send_at(keys, val, Instant::now() + delay)
+    }
+
+    /// Sends the msgs as one contiguous segment, see `async_channel::Sender::send_all`.
+    ///
+    /// # Errors
+    /// will return `SendError` if the channel is closed.
+    pub fn send_all(&self, msgs: impl IntoIterator<Item = (Vec<K>, V)>) -> Result<(), SendError> {
+        self.chan.append_all(msgs).map(|_| ())
+    }
+
+    /// Sends a msg and waits for its delivery, up to the rendezvous timeout.
+    ///
+    /// # Errors
+    /// will return `SendError` if the channel is closed, or the msg is not
+    /// received in time.
+    pub fn send_sync(&self, keys: Vec<K>, val: V) -> Result<(), SendError> {
+        self.chan.send_sync(keys, val, 0)
+    }
+
+    /// The channel, for its stats and diagnostics.
+    #[must_use]
+    pub fn channel(&self) -> &Channel<K, V> {
+        &self.chan
+    }
+}
+
+impl<K, V> Clone for Sender<K, V>
+where
+    K: HyperKey + Clone,
+{
+    fn clone(&self) -> Self {
+        self.chan.add_sender();
+        Self {
+            chan: self.chan.clone(),
+        }
+    }
+}
+
+impl<K, V> Drop for Sender<K, V>
+where
+    K: HyperKey + Clone,
+{
+    fn drop(&mut self) {
+        self.chan.drop_sender();
+    }
+}
+
+impl<K, V> Drop for Receiver<K, V>
+where
+    K: HyperKey + Clone,
+{
+    fn drop(&mut self) {
+        self.chan.disconnected.store(true, Ordering::Release);
+        self.chan.closed.store(true, Ordering::Release);
+        self.chan.shared.signal.notify();
+    }
+}
+
+impl<K, V> Receiver<K, V>
+where
+    K: HyperKey + Send + Debug + Clone,
+    V: Send + Debug,
+{
+    /// Blocks until a msg is deliverable.
+    ///
+    /// # Errors
+    /// will return `RecvError` once every sender is dropped and the channel
+    /// is drained.
+    pub fn recv(&self) -> Result<Msg<K, V>, RecvError> {
+        self.chan.recv_sync()
+    }
+
+    /// Receives a msg if one is deliverable right now.
+    ///
+    /// # Errors
+    /// will return `RecvError` if no msg is deliverable.
+    pub fn try_recv(&self) -> Result<Msg<K, V>, RecvError> {
+        self.chan.try_recv()
+    }
+
+    /// Same as `recv`, but receives up to `max` non-colliding msgs.
+    ///
+    /// # Errors
+    /// will return `RecvError` once every sender is dropped and the channel
+    /// is drained.
+    pub fn recv_batch(&self, max: usize) -> Result<Vec<Msg<K, V>>, RecvError> {
+        self.chan.recv_batch_sync(max)
+    }
+
+    /// Same as `recv_batch`, but waits up to `linger` for more msgs once the
+    /// first ones are received.
+    ///
+    /// # Errors
+    /// will return `RecvError` once every sender is dropped and the channel
+    /// is drained.
+    pub fn recv_batch_timeout(
+        &self,
+        max: usize,
+        linger: Duration,
+    ) -> Result<Vec<Msg<K, V>>, RecvError> {
+        self.chan.recv_batch_timeout_sync(max, linger)
+    }
+
+    /// Receives the first deliverable msg together with every pending msg
+    /// with the same keys.
+    ///
+    /// # Errors
+    /// will return `RecvError` once every sender is dropped and the channel
+    /// is drained.
+    pub fn recv_group(&self) -> Result<MsgGroup<K, V>, RecvError> {
+        self.chan.recv_group_sync()
+    }
+
+    /// Blocks for every msg until every sender is dropped and the channel is
+    /// drained.
+    #[must_use]
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        Iter { chan: &self.chan }
+    }
+
+    /// Drains the msgs which are deliverable right now.
+    #[must_use]
+    pub fn try_iter(&self) -> TryIter<'_, K, V> {
+        TryIter { chan: &self.chan }
+    }
+
+    /// The channel, for its stats and diagnostics.
+    #[must_use]
+    pub fn channel(&self) -> &Channel<K, V> {
+        &self.chan
+    }
+}
+
+impl<'a, K, V> IntoIterator for &'a Receiver<K, V>
+where
+    K: HyperKey + Send + Debug + Clone,
+    V: Send + Debug,
+{
+    type Item = Msg<K, V>;
+    type IntoIter = Iter<'a, K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<K, V> IntoIterator for Receiver<K, V>
+where
+    K: HyperKey + Send + Debug + Clone,
+    V: Send + Debug,
+{
+    type Item = Msg<K, V>;
+    type IntoIter = IntoIter<K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter { receiver: self }
+    }
+}
+
+/// Iterator owning the receiver, blocks for every msg until every sender is
+/// dropped and the channel is drained. The channel is closed when it's
+/// dropped.
+pub struct IntoIter<K, V>
+where
+    K: HyperKey + Clone,
+{
+    receiver: Receiver<K, V>,
+}
+
+impl<K, V> Iterator for IntoIter<K, V>
+where
+    K: HyperKey + Send + Debug + Clone,
+    V: Send + Debug,
+{
+    type Item = Msg<K, V>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.receiver.recv().ok()
+    }
+}
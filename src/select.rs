@@ -0,0 +1,269 @@
+use crate::signal::{park_until, Signal};
+use crate::{async_channel, owned_channel, sync_channel, Channel, HyperKey, Msg, RecvError};
+use std::cell::Cell;
+use std::fmt::Debug;
+use std::time::{Duration, Instant};
+
+thread_local! {
+    /// the arm the next attempt of this thread starts from.
+    static NEXT_ARM: Cell<usize> = const { Cell::new(0) };
+}
+
+/// Receivers which can be registered in a `Select`.
+pub trait Selectable {
+    type Key: HyperKey + Send + Debug + Clone;
+    type Val: Send + Debug;
+
+    fn channel(&self) -> &Channel<Self::Key, Self::Val>;
+}
+
+impl<K, V> Selectable for async_channel::Receiver<'_, K, V>
+where
+    K: HyperKey + Send + Debug + Clone,
+    V: Send + Debug,
+{
+    type Key = K;
+    type Val = V;
+
+    fn channel(&self) -> &Channel<K, V> {
+        self.chan
+    }
+}
+
+impl<K, V> Selectable for sync_channel::Receiver<'_, K, V>
+where
+    K: HyperKey + Send + Debug + Clone,
+    V: Send + Debug,
+{
+    type Key = K;
+    type Val = V;
+
+    fn channel(&self) -> &Channel<K, V> {
+        self.chan
+    }
+}
+
+impl<K, V> Selectable for owned_channel::Receiver<K, V>
+where
+    K: HyperKey + Send + Debug + Clone,
+    V: Send + Debug,
+{
+    type Key = K;
+    type Val = V;
+
+    fn channel(&self) -> &Channel<K, V> {
+        &self.chan
+    }
+}
+
+trait Arm<R> {
+    /// delivers a msg to the handler if the channel has a deliverable one.
+    fn try_select(&mut self) -> Option<R>;
+
+    /// the channel is closed and every msg is delivered.
+    fn is_drained(&self) -> bool;
+
+    /// the earliest instant a scheduled msg of the channel matures at.
+    fn next_maturity(&self) -> Option<Instant>;
+
+    /// a sender of the channel claimed a slot it didn't write yet.
+    fn has_unwritten(&self) -> bool;
+
+    /// unparks the threads waiting for the channel to change.
+    fn signal(&self) -> &Signal;
+}
+
+struct RecvArm<'a, K, V, F>
+where
+    K: HyperKey + Clone,
+{
+    chan: &'a Channel<K, V>,
+    handler: Option<F>,
+}
+
+impl<K, V, F, R> Arm<R> for RecvArm<'_, K, V, F>
+where
+    K: HyperKey + Send + Debug + Clone,
+    V: Send + Debug,
+    F: FnOnce(Msg<K, V>) -> R,
+{
+    fn try_select(&mut self) -> Option<R> {
+        let msg = self.chan.try_recv().ok()?;
+        // an arm is selected at most once, `wait` returns right after.
+        Some((self.handler.take().unwrap())(msg))
+    }
+
+    fn is_drained(&self) -> bool {
+        self.chan.is_closed() && !self.chan.has_pending()
+    }
+
+    fn next_maturity(&self) -> Option<Instant> {
+        self.chan.next_maturity()
+    }
+
+    fn has_unwritten(&self) -> bool {
+        self.chan.has_unwritten()
+    }
+
+    fn signal(&self) -> &Signal {
+        &self.chan.shared.signal
+    }
+}
+
+/// Waits on several channels, possibly of different key types, and hands the
+/// first deliverable msg to the handler of its channel.
+///
+/// ```ignore
+/// let val = Select::new()
+///     .recv(&rx1, |msg| msg.val)
+///     .recv(&rx2, |msg| msg.val.len())
+///     .timeout(Duration::from_millis(10), || 0)
+///     .wait();
+/// ```
+pub struct Select<'a, R> {
+    arms: Vec<Box<dyn Arm<R> + 'a>>,
+    default: Option<Box<dyn FnOnce() -> R + 'a>>,
+    timeout: Option<(Duration, Box<dyn FnOnce() -> R + 'a>)>,
+}
+
+impl<R> Default for Select<'_, R> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a, R> Select<'a, R> {
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            arms: vec![],
+            default: None,
+            timeout: None,
+        }
+    }
+
+    /// Registers a receiver, `f` is called with its msg if it's selected.
+    #[must_use]
+    pub fn recv<S, F>(mut self, receiver: &'a S, f: F) -> Self
+    where
+        S: Selectable,
+        S::Key: 'a,
+        S::Val: 'a,
+        F: FnOnce(Msg<S::Key, S::Val>) -> R + 'a,
+    {
+        self.arms.push(Box::new(RecvArm {
+            chan: receiver.channel(),
+            handler: Some(f),
+        }));
+        self
+    }
+
+    /// Called instead of blocking when no channel has a deliverable msg.
+    #[must_use]
+    pub fn default(mut self, f: impl FnOnce() -> R + 'a) -> Self {
+        self.default = Some(Box::new(f));
+        self
+    }
+
+    /// Called when no channel has a deliverable msg within `timeout`.
+    #[must_use]
+    pub fn timeout(mut self, timeout: Duration, f: impl FnOnce() -> R + 'a) -> Self {
+        self.timeout = Some((timeout, Box::new(f)));
+        self
+    }
+
+    /// Blocks until one of the channels delivers a msg, the timeout elapses,
+    /// or returns the default right away.
+    ///
+    /// Each attempt starts from the channel after the one the previous
+    /// attempt started from, so a busy channel doesn't starve the others.
+    /// The thread is parked between attempts until a msg is sent or
+    /// released, or a scheduled msg matures.
+    ///
+    /// # Errors
+    /// will return `RecvError` if every channel is closed and drained.
+    pub fn wait(mut self) -> Result<R, RecvError> {
+        let beginning = Instant::now();
+        let thread = crate::sync::current();
+        loop {
+            // registered before the attempt, a change made after it unparks
+            // this thread.
+            for arm in &self.arms {
+                arm.signal().register(&thread);
+            }
+            // checked before the scan, msgs sent before `close` are seen by it.
+            let drained = self.arms.iter().all(|arm| arm.is_drained());
+            let selected = self.try_select();
+            let deadline = self
+                .timeout
+                .as_ref()
+                .map(|(timeout, _)| beginning + *timeout);
+            let timed_out = deadline.is_some_and(|deadline| Instant::now() >= deadline);
+            if selected.is_none() && self.default.is_none() && !timed_out && !drained {
+                // its sender may not unpark this thread, see `Channel::has_unwritten`.
+                if self.arms.iter().any(|arm| arm.has_unwritten()) {
+                    crate::sync::yield_now();
+                } else {
+                    let maturity = self.arms.iter().filter_map(|arm| arm.next_maturity()).min();
+                    park_until(deadline.into_iter().chain(maturity).min());
+                }
+            }
+            for arm in &self.arms {
+                arm.signal().unregister(&thread);
+            }
+
+            if let Some(res) = selected {
+                return Ok(res);
+            }
+            if let Some(default) = self.default.take() {
+                return Ok(default());
+            }
+            if let Some((_, f)) = self.timeout.take_if(|_| timed_out) {
+                return Ok(f());
+            }
+            if drained {
+                return Err(RecvError);
+            }
+        }
+    }
+
+    /// tries every arm once, from the one after the arm this thread's
+    /// previous attempt started from.
+    fn try_select(&mut self) -> Option<R> {
+        let len = self.arms.len();
+        let start = NEXT_ARM.with(|next| next.replace(next.get().wrapping_add(1)));
+        (0..len).find_map(|i| self.arms[start.wrapping_add(i) % len].try_select())
+    }
+}
+
+/// Builds a `Select` from arms and waits on it.
+///
+/// ```ignore
+/// let res = select! {
+///     recv(rx1) -> msg => msg.val,
+///     recv(rx2) -> msg => msg.val.len(),
+///     timeout(Duration::from_millis(10)) => 0,
+/// };
+/// ```
+///
+/// A `default => expr` arm returns right away when nothing is deliverable.
+/// Arm bodies run inside closures, so `return` and `break` don't leave the
+/// enclosing function or loop.
+#[macro_export]
+macro_rules! select {
+    (@arms $sel:expr;) => {
+        $sel.wait()
+    };
+    (@arms $sel:expr; recv($rx:expr) -> $msg:pat_param => $body:expr $(, $($rest:tt)*)?) => {
+        $crate::select!(@arms $sel.recv(&$rx, |$msg| $body); $($($rest)*)?)
+    };
+    (@arms $sel:expr; timeout($timeout:expr) => $body:expr $(, $($rest:tt)*)?) => {
+        $crate::select!(@arms $sel.timeout($timeout, || $body); $($($rest)*)?)
+    };
+    (@arms $sel:expr; default => $body:expr $(, $($rest:tt)*)?) => {
+        $crate::select!(@arms $sel.default(|| $body); $($($rest)*)?)
+    };
+    ($($arms:tt)*) => {
+        $crate::select!(@arms $crate::select::Select::new(); $($arms)*)
+    };
+}
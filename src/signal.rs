@@ -0,0 +1,79 @@
+use crate::sync::{current, fence, park, park_timeout, AtomicUsize, Mutex, Ordering, Thread};
+use std::time::Instant;
+
+/// Parks the receivers waiting for a `Channel` to change: a msg is sent or
+/// released, or the channel is closed, see `notify`.
+#[derive(Debug, Default)]
+pub(crate) struct Signal {
+    /// threads registered in `parked`, checked before taking the lock.
+    waiting: AtomicUsize,
+    parked: Mutex<Vec<Thread>>,
+}
+
+impl Signal {
+    /// wakes every parked thread up, they check again what they wait for.
+    pub(crate) fn notify(&self) {
+        // pairs with the fence in `register`: either the waiter sees the
+        // change, or this thread sees the waiter.
+        fence(Ordering::SeqCst);
+        if self.waiting.load(Ordering::Acquire) == 0 {
+            return;
+        }
+        self.unpark_all();
+    }
+
+    /// same as `notify` once a msg is written, without the fence: the slot
+    /// was claimed by a `SeqCst` fetch-add, which a waiter registered since
+    /// then sees, see `Channel::has_unwritten`.
+    pub(crate) fn notify_written(&self) {
+        if self.waiting.load(Ordering::SeqCst) == 0 {
+            return;
+        }
+        self.unpark_all();
+    }
+
+    fn unpark_all(&self) {
+        let parked = std::mem::take(&mut *self.parked.lock().unwrap());
+        for thread in parked {
+            thread.unpark();
+        }
+    }
+
+    /// parks until `deadline` or until notified, unless `ready` holds once
+    /// the thread is registered. may return early, the caller checks again.
+    pub(crate) fn wait(&self, deadline: Instant, ready: impl FnOnce() -> bool) {
+        let thread = current();
+        self.register(&thread);
+        if !ready() {
+            park_until(Some(deadline));
+        }
+        self.unregister(&thread);
+    }
+
+    /// `thread` is unparked by the next `notify`. the changes made before
+    /// are seen by the thread once it returns.
+    pub(crate) fn register(&self, thread: &Thread) {
+        self.parked.lock().unwrap().push(thread.clone());
+        // published along with the registration, see `notify`.
+        self.waiting.fetch_add(1, Ordering::SeqCst);
+        fence(Ordering::SeqCst);
+    }
+
+    pub(crate) fn unregister(&self, thread: &Thread) {
+        self.waiting.fetch_sub(1, Ordering::Relaxed);
+        // still registered unless notified.
+        self.parked
+            .lock()
+            .unwrap()
+            .retain(|parked| parked.id() != thread.id());
+    }
+}
+
+/// parks the current thread until `deadline`, for good if `None`, or until
+/// unparked. may return early.
+pub(crate) fn park_until(deadline: Option<Instant>) {
+    match deadline {
+        Some(deadline) => park_timeout(deadline.saturating_duration_since(Instant::now())),
+        None => park(),
+    }
+}
@@ -0,0 +1,197 @@
+use crate::sync::{AtomicUsize, Ordering};
+use std::sync::atomic::{self, AtomicU64};
+use std::time::Duration;
+
+/// A snapshot of the state of a `Channel`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Stats {
+    /// msgs sent but not delivered yet, scheduled ones included.
+    pub pending: usize,
+    /// pending msgs which the last receive found colliding with the active
+    /// keys or with an earlier pending msg.
+    pub blocked: usize,
+    /// keys held by the delivered msgs which are not dropped yet.
+    pub active_keys: usize,
+    /// msgs sent since the channel was created, folded ones included.
+    pub sent: usize,
+    /// msgs folded into a pending msg instead of being appended.
+    pub coalesced: usize,
+    /// msgs handed out by the receiver.
+    pub delivered: usize,
+    /// delivered msgs which are dropped, so their keys are released.
+    pub released: usize,
+    /// delivered msgs whose slots are not reused yet. the slots are
+    /// reclaimed a block at a time, once no thread reads the block anymore.
+    pub awaiting_reclamation: usize,
+    /// blocks of slots allocated since the channel was created, it stops
+    /// growing once the delivered blocks are reused.
+    pub allocated_blocks: usize,
+}
+
+/// The counters behind `Stats`, shared by the channel and its msgs.
+#[derive(Debug, Default)]
+pub(crate) struct Counters {
+    pub(crate) sent: AtomicUsize,
+    pub(crate) coalesced: AtomicUsize,
+    pub(crate) delivered: AtomicUsize,
+    pub(crate) released: AtomicUsize,
+    /// msgs in a blocking episode, see `Stats::blocked`.
+    pub(crate) blocked_msgs: AtomicUsize,
+    pub(crate) queueing: AtomicHistogram,
+    pub(crate) blocked: AtomicHistogram,
+    pub(crate) hold: AtomicHistogram,
+}
+
+/// Latency histograms of the msgs of a `Channel`, telling a slow queue from
+/// contended keys from slow consumers.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Latencies {
+    /// from the send, or the maturity of a scheduled msg, to the delivery,
+    /// minus the time the msg was blocked.
+    pub queueing: Histogram,
+    /// time a pending msg was blocked by the active keys or by an earlier
+    /// pending msg.
+    pub blocked: Histogram,
+    /// from the delivery to the drop of the msg, when its keys are released.
+    pub hold: Histogram,
+}
+
+const BUCKETS: usize = 40;
+
+/// A histogram of durations with power-of-two microsecond buckets: bucket `i`
+/// counts the durations below `2^i` µs which are not counted by a lower one,
+/// the last bucket counts everything above.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Histogram {
+    buckets: [usize; BUCKETS],
+    count: usize,
+    sum: Duration,
+    max: Duration,
+}
+
+impl Default for Histogram {
+    fn default() -> Self {
+        Self {
+            buckets: [0; BUCKETS],
+            count: 0,
+            sum: Duration::ZERO,
+            max: Duration::ZERO,
+        }
+    }
+}
+
+impl Histogram {
+    /// number of recorded durations.
+    #[must_use]
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    #[must_use]
+    pub fn sum(&self) -> Duration {
+        self.sum
+    }
+
+    #[must_use]
+    pub fn max(&self) -> Duration {
+        self.max
+    }
+
+    /// zero if nothing is recorded.
+    #[must_use]
+    pub fn mean(&self) -> Duration {
+        if self.count == 0 {
+            return Duration::ZERO;
+        }
+        let nanos = self.sum.as_nanos() / self.count as u128;
+        Duration::from_nanos(u64::try_from(nanos).unwrap_or(u64::MAX))
+    }
+
+    /// The upper bound of the bucket holding the `p`th percentile, capped by
+    /// the max. zero if nothing is recorded.
+    ///
+    /// # Panics
+    /// will panic if `p` is above 100.
+    #[must_use]
+    pub fn percentile(&self, p: usize) -> Duration {
+        assert!(p <= 100, "percentile above 100");
+        let rank = (self.count * p).div_ceil(100).max(1);
+        let mut seen = 0;
+        for (upper, count) in self.buckets() {
+            seen += count;
+            if seen >= rank {
+                return upper.min(self.max);
+            }
+        }
+        Duration::ZERO
+    }
+
+    /// The non-empty buckets as `(upper bound, count)`, shortest first.
+    pub fn buckets(&self) -> impl Iterator<Item = (Duration, usize)> + '_ {
+        self.buckets
+            .iter()
+            .enumerate()
+            .filter(|(_, &count)| count > 0)
+            .map(|(i, &count)| (upper_bound(i), count))
+    }
+}
+
+/// the upper bound of bucket `i`, the last one is unbounded.
+fn upper_bound(i: usize) -> Duration {
+    if i + 1 == BUCKETS {
+        Duration::MAX
+    } else {
+        Duration::from_micros(1 << i)
+    }
+}
+
+/// The recording side of a `Histogram`, shared by the channel and its msgs.
+///
+/// only read by `snapshot`, it never decides what the channel does, so it
+/// stays on the std atomics under loom, out of the explored interleavings.
+#[derive(Debug)]
+pub(crate) struct AtomicHistogram {
+    buckets: [atomic::AtomicUsize; BUCKETS],
+    count: atomic::AtomicUsize,
+    sum_nanos: AtomicU64,
+    max_nanos: AtomicU64,
+}
+
+impl Default for AtomicHistogram {
+    fn default() -> Self {
+        Self {
+            buckets: std::array::from_fn(|_| atomic::AtomicUsize::new(0)),
+            count: atomic::AtomicUsize::new(0),
+            sum_nanos: AtomicU64::new(0),
+            max_nanos: AtomicU64::new(0),
+        }
+    }
+}
+
+impl AtomicHistogram {
+    /// records `duration` `n` times.
+    pub(crate) fn record_n(&self, duration: Duration, n: usize) {
+        if n == 0 {
+            return;
+        }
+        let micros = u64::try_from(duration.as_micros()).unwrap_or(u64::MAX);
+        // the bit length of `micros`: the first bucket whose bound is above it.
+        let bucket = ((u64::BITS - micros.leading_zeros()) as usize).min(BUCKETS - 1);
+        let nanos = u64::try_from(duration.as_nanos()).unwrap_or(u64::MAX);
+
+        self.buckets[bucket].fetch_add(n, Ordering::Relaxed);
+        self.count.fetch_add(n, Ordering::Relaxed);
+        self.sum_nanos
+            .fetch_add(nanos.saturating_mul(n as u64), Ordering::Relaxed);
+        self.max_nanos.fetch_max(nanos, Ordering::Relaxed);
+    }
+
+    pub(crate) fn snapshot(&self) -> Histogram {
+        Histogram {
+            buckets: std::array::from_fn(|i| self.buckets[i].load(Ordering::Relaxed)),
+            count: self.count.load(Ordering::Relaxed),
+            sum: Duration::from_nanos(self.sum_nanos.load(Ordering::Relaxed)),
+            max: Duration::from_nanos(self.max_nanos.load(Ordering::Relaxed)),
+        }
+    }
+}
@@ -0,0 +1,28 @@
+// The atomics and locks of the channel, its filter and its owned handles,
+// swapped for Loom's under `cfg(loom)` so the tests in `tests/loom.rs` can
+// explore their interleavings:
+//
+//     RUSTFLAGS="--cfg loom" cargo test --release --test loom
+
+#[cfg(loom)]
+pub(crate) use loom::{
+    hint::spin_loop,
+    sync::atomic::{fence, AtomicBool, AtomicPtr, AtomicU8, AtomicUsize, Ordering},
+    sync::{Arc, Mutex},
+    thread::{current, park, yield_now, Thread},
+};
+
+/// loom has no timed park, the thread yields and wakes up as if the timeout
+/// went by.
+#[cfg(loom)]
+pub(crate) fn park_timeout(_: std::time::Duration) {
+    loom::thread::yield_now();
+}
+
+#[cfg(not(loom))]
+pub(crate) use std::{
+    hint::spin_loop,
+    sync::atomic::{fence, AtomicBool, AtomicPtr, AtomicU8, AtomicUsize, Ordering},
+    sync::{Arc, Mutex},
+    thread::{current, park, park_timeout, yield_now, Thread},
+};
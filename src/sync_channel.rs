@@ -1,12 +1,15 @@
-use crate::{Channel, HyperKey, Msg, RecvError, SendError};
+use crate::{Channel, HyperKey, Iter, Msg, MsgGroup, RecvError, SendError, TryIter};
 use std::fmt::Debug;
+use std::time::{Duration, Instant};
 
+/// A sender borrowing the channel, the channel is closed when the last one
+/// is dropped.
 pub struct Sender<'a, K, V>
 where
     K: HyperKey + Send + Debug + Clone,
     V: Send + Debug,
 {
-    pub chan: &'a Channel<K, V>,
+    chan: &'a Channel<K, V>,
 }
 
 pub struct Receiver<'a, K, V>
@@ -22,12 +25,85 @@ where
     K: HyperKey + Send + Debug + Clone,
     V: Send + Debug,
 {
-    /// # Errors
-    /// will return `SendError` if data structure panics in the Channel.
+    /// Blocks until a msg is deliverable, a msg whose keys collide with the
+    /// active keys waits for them to be released instead of failing.
     ///
+    /// # Errors
+    /// will return `RecvError` once the channel is closed and drained.
     pub fn recv(&self) -> Result<Msg<K, V>, RecvError> {
         self.chan.recv_sync()
     }
+
+    /// Receives up to `max` msgs which collide neither with the active keys
+    /// nor with each other, their keys are registered at once.
+    ///
+    /// # Errors
+    /// will return `RecvError` once the channel is closed and drained.
+    pub fn recv_batch(&self, max: usize) -> Result<Vec<Msg<K, V>>, RecvError> {
+        self.chan.recv_batch_sync(max)
+    }
+
+    /// Same as `recv_batch`, but once the first msgs are received, waits up
+    /// to `linger` for more msgs to become deliverable, trading latency for
+    /// larger batches.
+    ///
+    /// # Errors
+    /// will return `RecvError` once the channel is closed and drained.
+    pub fn recv_batch_timeout(
+        &self,
+        max: usize,
+        linger: Duration,
+    ) -> Result<Vec<Msg<K, V>>, RecvError> {
+        self.chan.recv_batch_timeout_sync(max, linger)
+    }
+
+    /// Receives the first deliverable msg together with every pending msg
+    /// with the same keys, the keys are acquired once for the whole group.
+    ///
+    /// # Errors
+    /// will return `RecvError` once the channel is closed and drained.
+    pub fn recv_group(&self) -> Result<MsgGroup<K, V>, RecvError> {
+        self.chan.recv_group_sync()
+    }
+
+    /// Blocks for every msg until every sender is dropped, or the channel is
+    /// closed, and the channel is drained.
+    #[must_use]
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        Iter { chan: self.chan }
+    }
+
+    /// Drains the msgs which are deliverable right now.
+    #[must_use]
+    pub fn try_iter(&self) -> TryIter<'_, K, V> {
+        TryIter { chan: self.chan }
+    }
+}
+
+impl<'a, K, V> IntoIterator for &'a Receiver<'_, K, V>
+where
+    K: HyperKey + Send + Debug + Clone,
+    V: Send + Debug,
+{
+    type Item = Msg<K, V>;
+    type IntoIter = Iter<'a, K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, K, V> IntoIterator for Receiver<'a, K, V>
+where
+    K: HyperKey + Send + Debug + Clone,
+    V: Send + Debug,
+{
+    type Item = Msg<K, V>;
+    type IntoIter = Iter<'a, K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        Iter { chan: self.chan }
+    }
 }
 
 impl<K, V> Sender<'_, K, V>
@@ -36,10 +112,78 @@ where
     V: Send + Debug,
 {
     /// # Errors
-    /// will return `SendError` if inner structures panics.
+    /// will return `SendError` if the channel is closed, or the msg is not
+    /// received in time.
     ///
     pub fn send(&self, keys: Vec<K>, val: V) -> Result<(), SendError> {
-        self.chan.send_sync(keys, val)
+        self.chan.send_sync(keys, val, 0)
+    }
+
+    /// Sends the msgs as one contiguous segment, the receiver sees either all
+    /// of them or none, and no other sender's msg is interleaved. Blocks until
+    /// every msg is received.
+    ///
+    /// On a folding channel these msgs are never folded into pending ones.
+    ///
+    /// # Errors
+    /// will return `SendError` if the channel is closed, or the msgs are not
+    /// received in time.
+    pub fn send_all(&self, msgs: impl IntoIterator<Item = (Vec<K>, V)>) -> Result<(), SendError> {
+        self.chan.send_all_sync(msgs)
+    }
+
+    /// Sends a msg which is delivered before lower priority msgs, unless an
+    /// earlier msg with colliding keys is still pending.
+    ///
+    /// # Errors
+    /// will return `SendError` if the channel is closed, or the msg is not
+    /// received in time.
+    pub fn send_with_priority(&self, keys: Vec<K>, val: V, priority: u8) -> Result<(), SendError> {
+        self.chan.send_sync(keys, val, priority)
+    }
+
+    /// Sends a msg which is invisible to the receiver until `at`, and blocks
+    /// until it is received. The rendezvous timeout starts at `at`.
+    ///
+    /// # Errors
+    /// will return `SendError` if the channel is closed, or the msg is not
+    /// received in time.
+    pub fn send_at(&self, keys: Vec<K>, val: V, at: Instant) -> Result<(), SendError> {
+        self.chan.send_at_sync(keys, val, at)
+    }
+
+    /// Same as `send_at`, `delay` from now.
+    ///
+    /// # Errors
+    /// will return `SendError` if the channel is closed, or the msg is not
+    /// received in time.
+    pub fn send_after(&self, keys: Vec<K>, val: V, delay: Duration) -> Result<(), SendError> {
+        self.send_at(keys, val, Instant::now() + delay)
+    }
+}
+
+impl<'a, K, V> Receiver<'a, K, V>
+where
+    K: HyperKey + Send + Clone,
+    V: Send,
+{
+    #[must_use]
+    pub fn new(chan: &'a Channel<K, V>) -> Self {
+        Self { chan }
+    }
+}
+
+impl<'a, K, V> Sender<'a, K, V>
+where
+    K: HyperKey + Send + Debug + Clone,
+    V: Send + Debug,
+{
+    /// A sender made once the channel is closed, e.g. after the last sender
+    /// was dropped, can't send anymore.
+    #[must_use]
+    pub fn new(chan: &'a Channel<K, V>) -> Self {
+        chan.add_sender();
+        Self { chan }
     }
 }
 
@@ -49,8 +193,16 @@ where
     V: Send + Debug,
 {
     fn clone(&self) -> Self {
-        Self {
-            chan: <&Channel<K, V>>::clone(&self.chan),
-        }
+        Self::new(self.chan)
+    }
+}
+
+impl<K, V> Drop for Sender<'_, K, V>
+where
+    K: HyperKey + Send + Debug + Clone,
+    V: Send + Debug,
+{
+    fn drop(&mut self) {
+        self.chan.drop_sender();
     }
 }
@@ -0,0 +1,56 @@
+use crate::key_filter::{Filter, StuckKey};
+use crate::HyperKey;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+/// Watches the hold durations of the active keys of a `Channel`, see
+/// `Channel::watchdog`. Dropping it stops the watchdog.
+pub struct Watchdog {
+    stopped: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Watchdog {
+    pub(crate) fn spawn<K>(
+        filter: Filter<K>,
+        threshold: Duration,
+        mut on_stuck: impl FnMut(StuckKey<K>) + Send + 'static,
+    ) -> Self
+    where
+        K: HyperKey + Send + Clone + 'static,
+    {
+        let stopped = Arc::new(AtomicBool::new(false));
+        // polls often enough to report close to the threshold, and to stop soon.
+        let period = (threshold / 4).clamp(Duration::from_millis(1), Duration::from_millis(50));
+
+        filter.watch();
+        let thread = {
+            let stopped = stopped.clone();
+            thread::spawn(move || {
+                while !stopped.load(Ordering::SeqCst) {
+                    for stuck in filter.stuck(threshold) {
+                        on_stuck(stuck);
+                    }
+                    thread::sleep(period);
+                }
+                filter.unwatch();
+            })
+        };
+
+        Self {
+            stopped,
+            thread: Some(thread),
+        }
+    }
+}
+
+impl Drop for Watchdog {
+    fn drop(&mut self) {
+        self.stopped.store(true, Ordering::SeqCst);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
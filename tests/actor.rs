@@ -0,0 +1,184 @@
+use mpsc::actor::{Actor, Config, Handler};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct UsizeTest {
+    key: usize,
+}
+
+impl mpsc::HyperKey for UsizeTest {
+    fn collision_detect(&self, other: &Self) -> bool {
+        self.key == other.key
+    }
+}
+
+type Done = Arc<Mutex<Vec<(usize, Vec<usize>)>>>;
+
+/// appends every value to the state, and records the state when it reaches 100.
+struct Collect {
+    done: Done,
+}
+
+impl Handler<UsizeTest, usize> for Collect {
+    type State = (usize, Vec<usize>);
+
+    fn init(&self, key: &UsizeTest) -> Self::State {
+        (key.key, vec![])
+    }
+
+    fn handle(&self, state: &mut Self::State, val: usize) {
+        state.1.push(val);
+        if state.1.len() == 100 {
+            self.done.lock().unwrap().push(state.clone());
+        }
+    }
+}
+
+#[test]
+fn per_key_order_test() {
+    let done = Arc::new(Mutex::new(vec![]));
+    let actor = Actor::spawn(Collect { done: done.clone() }, Config::default());
+
+    for i in 0..100 {
+        for key in 0..8 {
+            actor.send(UsizeTest { key }, i);
+        }
+    }
+    drop(actor);
+
+    let done = done.lock().unwrap();
+    assert_eq!(done.len(), 8);
+    for (_, vals) in done.iter() {
+        assert_eq!(*vals, (0..100).collect::<Vec<_>>());
+    }
+}
+
+#[test]
+fn evict_test() {
+    let done = Arc::new(Mutex::new(vec![]));
+    let actor = Actor::spawn(
+        Collect { done: done.clone() },
+        Config {
+            workers: 2,
+            idle_timeout: Some(Duration::from_millis(50)),
+        },
+    );
+
+    for i in 0..50 {
+        actor.send(UsizeTest { key: 1 }, i);
+    }
+    while actor.states_len() == 0 {
+        thread::yield_now();
+    }
+    thread::sleep(Duration::from_millis(200));
+    assert_eq!(actor.states_len(), 0);
+
+    // the evicted state starts over.
+    for i in 0..100 {
+        actor.send(UsizeTest { key: 1 }, i);
+    }
+    drop(actor);
+    assert_eq!(
+        *done.lock().unwrap(),
+        vec![(1, (0..100).collect::<Vec<_>>())]
+    );
+}
+
+/// panics on odd values.
+struct Fragile {
+    done: Done,
+}
+
+impl Handler<UsizeTest, usize> for Fragile {
+    type State = (usize, Vec<usize>);
+
+    fn init(&self, key: &UsizeTest) -> Self::State {
+        (key.key, vec![])
+    }
+
+    fn handle(&self, state: &mut Self::State, val: usize) {
+        assert!(val.is_multiple_of(2), "odd value");
+        state.1.push(val);
+        self.done.lock().unwrap().push(state.clone());
+    }
+}
+
+#[test]
+fn panic_test() {
+    let done = Arc::new(Mutex::new(vec![]));
+    let actor = Actor::spawn(
+        Fragile { done: done.clone() },
+        Config {
+            workers: 2,
+            idle_timeout: None,
+        },
+    );
+
+    for i in 0..10 {
+        actor.send(UsizeTest { key: 1 }, i);
+        actor.send(UsizeTest { key: 2 }, i * 2);
+    }
+    // returns although half of the msgs panicked.
+    drop(actor);
+
+    let done = done.lock().unwrap();
+    // the state of key 1 starts over after every panic.
+    assert!(done
+        .iter()
+        .filter(|(key, _)| *key == 1)
+        .all(|(_, vals)| vals.len() == 1));
+    assert_eq!(done.iter().filter(|(key, _)| *key == 1).count(), 5);
+    let key2 = done.iter().rfind(|(key, _)| *key == 2).unwrap();
+    assert_eq!(key2.1, (0..10).map(|i| i * 2).collect::<Vec<_>>());
+}
+
+/// sleeps longer than the idle timeout on the first value.
+struct Slow {
+    done: Done,
+}
+
+impl Handler<UsizeTest, usize> for Slow {
+    type State = (usize, Vec<usize>);
+
+    fn init(&self, key: &UsizeTest) -> Self::State {
+        (key.key, vec![])
+    }
+
+    fn handle(&self, state: &mut Self::State, val: usize) {
+        if val == 0 {
+            thread::sleep(Duration::from_millis(100));
+        }
+        state.1.push(val);
+        self.done.lock().unwrap().push(state.clone());
+    }
+}
+
+#[test]
+fn slow_handler_evict_test() {
+    let done = Arc::new(Mutex::new(vec![]));
+    let actor = Actor::spawn(
+        Slow { done: done.clone() },
+        Config {
+            workers: 2,
+            idle_timeout: Some(Duration::from_millis(20)),
+        },
+    );
+
+    for _ in 0..5 {
+        for i in 0..3 {
+            actor.send(UsizeTest { key: 1 }, i);
+        }
+        // evicted in between, the next round starts over.
+        thread::sleep(Duration::from_millis(250));
+    }
+    drop(actor);
+
+    // the state is never evicted while a msg of the round is handled.
+    let done = done.lock().unwrap();
+    assert_eq!(done.len(), 15);
+    for round in done.chunks(3) {
+        assert_eq!(round[2].1, vec![0, 1, 2]);
+    }
+}
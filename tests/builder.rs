@@ -0,0 +1,286 @@
+use mpsc::builder::{ChannelBuilder, OrderingMode, WaitStrategy};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+#[derive(Debug, Clone)]
+struct UsizeTest {
+    key: usize,
+}
+
+impl mpsc::HyperKey for UsizeTest {
+    fn collision_detect(&self, other: &Self) -> bool {
+        self.key == other.key
+    }
+}
+
+#[test]
+fn owned_test() {
+    let (sender, receiver) = ChannelBuilder::<UsizeTest, usize>::new()
+        .name("owned")
+        .wait_strategy(WaitStrategy::Yield)
+        .build();
+    assert_eq!(receiver.channel().name(), Some("owned"));
+
+    let handles: Vec<_> = (0..4)
+        .map(|key| {
+            let sender = sender.clone();
+            thread::spawn(move || {
+                for i in 0..25 {
+                    sender.send(vec![UsizeTest { key }], key * 25 + i).unwrap();
+                }
+            })
+        })
+        .collect();
+    drop(sender);
+    for handle in handles {
+        handle.join().unwrap();
+    }
+
+    // the last sender closed the channel.
+    let mut vals: Vec<_> = receiver.iter().map(|msg| msg.val).collect();
+    vals.sort_unstable();
+    assert_eq!(vals, (0..100).collect::<Vec<_>>());
+    assert!(receiver.channel().is_closed());
+    assert!(receiver.recv().is_err());
+}
+
+#[test]
+fn capacity_test() {
+    let (sender, receiver) = ChannelBuilder::<UsizeTest, usize>::new()
+        .capacity(2)
+        .wait_strategy(WaitStrategy::Sleep(Duration::from_millis(1)))
+        .build();
+    let sent = Arc::new(Mutex::new(0));
+
+    let handle = {
+        let sent = sent.clone();
+        thread::spawn(move || {
+            for i in 0..5 {
+                sender.send(vec![UsizeTest { key: i }], i).unwrap();
+                *sent.lock().unwrap() += 1;
+            }
+        })
+    };
+    thread::sleep(Duration::from_millis(50));
+    assert_eq!(*sent.lock().unwrap(), 2);
+    assert_eq!(receiver.channel().pending_len(), 2);
+
+    let vals: Vec<_> = receiver.iter().map(|msg| msg.val).collect();
+    handle.join().unwrap();
+    assert_eq!(vals, (0..5).collect::<Vec<_>>());
+}
+
+#[test]
+fn capacity_send_all_test() {
+    let (sender, receiver) = ChannelBuilder::<UsizeTest, usize>::new()
+        .capacity(2)
+        .wait_strategy(WaitStrategy::Sleep(Duration::from_millis(1)))
+        .build();
+    sender.send(vec![UsizeTest { key: 0 }], 0).unwrap();
+
+    let handle = {
+        let sender = sender.clone();
+        // no room for both msgs, the segment isn't split either.
+        thread::spawn(move || {
+            sender
+                .send_all((1..3).map(|i| (vec![UsizeTest { key: i }], i)))
+                .unwrap();
+        })
+    };
+    let others: Vec<_> = (3..6)
+        .map(|i| {
+            let sender = sender.clone();
+            thread::spawn(move || sender.send(vec![UsizeTest { key: i }], i).unwrap())
+        })
+        .collect();
+    thread::sleep(Duration::from_millis(50));
+    assert_eq!(receiver.channel().pending_len(), 2);
+    drop(sender);
+
+    let mut vals: Vec<_> = receiver.iter().map(|msg| msg.val).collect();
+    handle.join().unwrap();
+    for other in others {
+        other.join().unwrap();
+    }
+    vals.sort_unstable();
+    assert_eq!(vals, (0..6).collect::<Vec<_>>());
+    // more msgs than the capacity wait for an empty channel.
+    let (sender, receiver) = ChannelBuilder::<UsizeTest, usize>::new()
+        .capacity(1)
+        .build();
+    sender
+        .send_all((0..3).map(|i| (vec![UsizeTest { key: i }], i)))
+        .unwrap();
+    assert_eq!(receiver.try_iter().count(), 3);
+}
+
+#[test]
+fn receiver_dropped_test() {
+    let (sender, receiver) = ChannelBuilder::<UsizeTest, usize>::new()
+        .capacity(1)
+        .wait_strategy(WaitStrategy::Sleep(Duration::from_millis(1)))
+        .build();
+    sender.send(vec![UsizeTest { key: 0 }], 0).unwrap();
+
+    let handle = {
+        let sender = sender.clone();
+        // waits for room until the receiver is gone.
+        thread::spawn(move || sender.send(vec![UsizeTest { key: 1 }], 1))
+    };
+    thread::sleep(Duration::from_millis(20));
+    drop(receiver);
+    assert!(handle.join().unwrap().is_err());
+    assert!(sender.send(vec![UsizeTest { key: 2 }], 2).is_err());
+}
+
+#[test]
+fn strict_test() {
+    let (sender, receiver) = ChannelBuilder::<UsizeTest, usize>::new()
+        .ordering(OrderingMode::Strict)
+        .build();
+    sender.send(vec![UsizeTest { key: 1 }], 1).unwrap();
+    sender.send(vec![UsizeTest { key: 1 }], 2).unwrap();
+    sender.send(vec![UsizeTest { key: 2 }], 3).unwrap();
+
+    let msg = receiver.try_recv().unwrap();
+    // the msg with key 2 doesn't overtake the blocked one.
+    assert!(receiver.try_recv().is_err());
+    drop(msg);
+    let vals: Vec<_> = receiver.try_iter().map(|msg| msg.val).collect();
+    assert_eq!(vals, vec![2, 3]);
+}
+
+#[derive(Default)]
+struct Expired {
+    keys: Mutex<Vec<usize>>,
+}
+
+impl mpsc::observer::ChannelObserver<UsizeTest> for Arc<Expired> {
+    fn on_expire(&self, keys: &[UsizeTest]) {
+        self.keys
+            .lock()
+            .unwrap()
+            .extend(keys.iter().map(|key| key.key));
+    }
+}
+
+#[test]
+fn rendezvous_timeout_test() {
+    let expired = Arc::new(Expired::default());
+    let (sender, receiver) = ChannelBuilder::<UsizeTest, usize>::new()
+        .rendezvous_timeout(Duration::from_millis(20))
+        .observer(expired.clone())
+        .build();
+
+    assert!(sender.send_sync(vec![UsizeTest { key: 7 }], 7).is_err());
+    assert_eq!(*expired.keys.lock().unwrap(), vec![7]);
+    // the msg stays pending.
+    assert_eq!(receiver.recv().unwrap().val, 7);
+}
+
+#[test]
+fn pool_test() {
+    let (sender, receiver) = ChannelBuilder::<UsizeTest, usize>::new()
+        .pool_cap(32)
+        .preallocate(64)
+        .build();
+    let handle = thread::spawn(move || {
+        for i in 0..1000 {
+            sender.send(vec![UsizeTest { key: i % 7 }], i).unwrap();
+        }
+    });
+
+    // the delivered blocks go back to the pool while the sender refills it.
+    let vals: Vec<_> = receiver.iter().map(|msg| msg.val).collect();
+    handle.join().unwrap();
+    assert_eq!(vals, (0..1000).collect::<Vec<_>>());
+    assert!(!receiver.channel().dump().contains("#1 delivered"));
+}
+
+#[test]
+fn sync_reclaim_test() {
+    let (sender, receiver) = ChannelBuilder::<UsizeTest, usize>::new()
+        .wait_strategy(WaitStrategy::Yield)
+        .build();
+    let handles: Vec<_> = (0..4)
+        .map(|t| {
+            let sender = sender.clone();
+            thread::spawn(move || {
+                for i in 0..1000 {
+                    if t == 0 {
+                        sender.send(vec![UsizeTest { key: i }], i).unwrap();
+                    } else {
+                        // always some sync sender waiting for a delivery.
+                        sender.send_sync(vec![UsizeTest { key: i }], i).unwrap();
+                    }
+                }
+            })
+        })
+        .collect();
+    drop(sender);
+
+    let chan = receiver.channel();
+    let mut allocated = vec![];
+    for (i, msg) in receiver.iter().enumerate() {
+        drop(msg);
+        if i % 1000 == 999 {
+            allocated.push(chan.stats().allocated_blocks);
+        }
+    }
+    for handle in handles {
+        handle.join().unwrap();
+    }
+
+    // the last 3000 msgs span 94 blocks, the delivered ones are reused
+    // although the sync senders are never all out of the channel.
+    assert!(allocated[3] - allocated[0] < 8, "{allocated:?}");
+    assert!(chan.stats().awaiting_reclamation < 8 * 32);
+}
+
+#[test]
+fn into_iter_test() {
+    let (sender, receiver) = ChannelBuilder::<UsizeTest, usize>::new().build();
+    let handle = thread::spawn(move || receiver.into_iter().map(|msg| msg.val).sum::<usize>());
+    for i in 0..10 {
+        sender.send(vec![UsizeTest { key: i % 3 }], i).unwrap();
+    }
+    drop(sender);
+    assert_eq!(handle.join().unwrap(), 45);
+}
+
+#[test]
+fn owned_select_test() {
+    let (sender1, receiver1) = ChannelBuilder::<UsizeTest, usize>::new().build();
+    let (sender2, receiver2) = ChannelBuilder::<UsizeTest, &str>::new().build();
+    sender2.send(vec![UsizeTest { key: 1 }], "two").unwrap();
+
+    let res = mpsc::select! {
+        recv(receiver1) -> msg => msg.val,
+        recv(receiver2) -> msg => msg.val.len(),
+    };
+    assert_eq!(res.unwrap(), 3);
+
+    drop(sender1);
+    drop(sender2);
+    let res = mpsc::select! {
+        recv(receiver1) -> msg => msg.val,
+        recv(receiver2) -> msg => msg.val.len(),
+    };
+    assert!(res.is_err());
+}
+
+#[test]
+fn mixed_senders_test() {
+    let (sender, receiver) = ChannelBuilder::<UsizeTest, usize>::new().build();
+    let chan = receiver.channel();
+    let borrowed = mpsc::async_channel::Sender::new(chan);
+
+    // the owned and the borrowed senders are counted together.
+    drop(sender);
+    assert!(!chan.is_closed());
+    borrowed.send(vec![UsizeTest { key: 1 }], 1).unwrap();
+    drop(borrowed);
+    assert!(chan.is_closed());
+    assert_eq!(receiver.recv().unwrap().val, 1);
+}
@@ -0,0 +1,222 @@
+#![cfg(loom)]
+
+// RUSTFLAGS="--cfg loom" cargo test --release --test loom
+
+use loom::thread;
+use mpsc::builder::ChannelBuilder;
+
+#[derive(Debug, Clone)]
+struct UsizeTest {
+    key: usize,
+}
+
+impl mpsc::HyperKey for UsizeTest {
+    fn collision_detect(&self, other: &Self) -> bool {
+        self.key == other.key
+    }
+}
+
+fn key(key: usize) -> Vec<UsizeTest> {
+    vec![UsizeTest { key }]
+}
+
+/// explores the interleavings with up to 4 preemptions, unless
+/// `LOOM_MAX_PREEMPTIONS` says otherwise. the recycling guards make the
+/// unbounded search intractable.
+fn model(f: impl Fn() + Sync + Send + 'static) {
+    model_bounded(4, f);
+}
+
+/// same as `model`, for the models spinning on a sync send.
+fn model_bounded(preemptions: usize, f: impl Fn() + Sync + Send + 'static) {
+    let mut builder = loom::model::Builder::new();
+    builder.preemption_bound.get_or_insert(preemptions);
+    builder.check(f);
+}
+
+#[test]
+fn concurrent_send_test() {
+    model(|| {
+        let (sender, receiver) = ChannelBuilder::<UsizeTest, usize>::new().build();
+        let senders: Vec<_> = (1..=2)
+            .map(|i| {
+                let sender = sender.clone();
+                thread::spawn(move || sender.send(key(i), i).unwrap())
+            })
+            .collect();
+        drop(sender);
+        for sender in senders {
+            sender.join().unwrap();
+        }
+
+        // both appends are linked, whatever the tail they started from.
+        let mut vals: Vec<_> = receiver.try_iter().map(|msg| msg.val).collect();
+        vals.sort_unstable();
+        assert_eq!(vals, [1, 2]);
+        assert_eq!(receiver.channel().pending_len(), 0);
+    });
+}
+
+#[test]
+fn send_recv_test() {
+    model(|| {
+        let (sender, receiver) = ChannelBuilder::<UsizeTest, usize>::new().build();
+        let producer = thread::spawn(move || {
+            sender.send(key(1), 1).unwrap();
+            sender.send(key(1), 2).unwrap();
+        });
+
+        // a msg is either fully linked or not seen at all.
+        let mut vals = Vec::new();
+        if let Ok(msg) = receiver.try_recv() {
+            vals.push(msg.val);
+        }
+        producer.join().unwrap();
+        vals.extend(receiver.try_iter().map(|msg| msg.val));
+        assert_eq!(vals, [1, 2]);
+    });
+}
+
+#[test]
+fn parked_recv_test() {
+    model(|| {
+        let (sender, receiver) = ChannelBuilder::<UsizeTest, usize>::new().build();
+        let producer = thread::spawn(move || sender.send(key(1), 1).unwrap());
+
+        // the write wakes up a receiver parked since the claim, or the
+        // receiver sees the slot claimed and waits for the write.
+        assert_eq!(receiver.recv().unwrap().val, 1);
+        producer.join().unwrap();
+    });
+}
+
+#[test]
+fn release_race_test() {
+    model(|| {
+        let (sender, receiver) = ChannelBuilder::<UsizeTest, usize>::new().build();
+        sender.send(key(1), 1).unwrap();
+        sender.send(key(1), 2).unwrap();
+        let first = receiver.try_recv().unwrap();
+        let releaser = thread::spawn(move || drop(first));
+
+        // the second msg is delivered only once the first one released key 1.
+        let second = receiver.try_recv();
+        releaser.join().unwrap();
+        let second = second.or_else(|_| receiver.try_recv()).unwrap();
+        assert_eq!(second.val, 2);
+        assert!(receiver.channel().is_key_active(&UsizeTest { key: 1 }));
+        drop(second);
+        assert!(receiver.channel().active_keys().is_empty());
+    });
+}
+
+#[test]
+fn drop_test() {
+    model(|| {
+        let (sender, receiver) = ChannelBuilder::<UsizeTest, usize>::new().build();
+        let producer = thread::spawn(move || {
+            // fails once the receiver is gone, then the next one fails too.
+            let first = sender.send(key(1), 1);
+            let second = sender.send(key(2), 2);
+            assert!(first.is_ok() || second.is_err());
+        });
+
+        // the msgs outlive the channel, which frees the pending ones.
+        let msg = receiver.try_recv();
+        drop(receiver);
+        producer.join().unwrap();
+        if let Ok(msg) = msg {
+            assert_eq!(msg.val, 1);
+        }
+    });
+}
+
+#[test]
+fn segment_test() {
+    model(|| {
+        let (sender, receiver) = ChannelBuilder::<UsizeTest, usize>::new().build();
+        let segment = sender.clone();
+        let producer = thread::spawn(move || {
+            segment
+                .send_all([(key(1), 1), (key(2), 2), (key(3), 3)])
+                .unwrap();
+        });
+        sender.send(key(4), 4).unwrap();
+
+        // the segment spans blocks, still it shows up all at once.
+        let chan = receiver.channel();
+        let seen = chan.pending_keys().filter(|keys| keys[0].key < 4).count();
+        assert!(seen == 0 || seen == 3);
+        producer.join().unwrap();
+
+        // and no other msg is interleaved.
+        let vals: Vec<_> = receiver.try_iter().map(|msg| msg.val).collect();
+        assert!(vals == [1, 2, 3, 4] || vals == [4, 1, 2, 3]);
+    });
+}
+
+#[test]
+fn reclaim_test() {
+    model(|| {
+        let (sender, receiver) = ChannelBuilder::<UsizeTest, usize>::new().build();
+        sender.send(key(1), 1).unwrap();
+        sender.send(key(2), 2).unwrap();
+        let producer = thread::spawn(move || {
+            sender.send(key(3), 3).unwrap();
+            sender.send(key(4), 4).unwrap();
+        });
+
+        // the first block is recycled while the sender may still walk it.
+        let mut vals: Vec<_> = receiver.try_iter().map(|msg| msg.val).collect();
+        producer.join().unwrap();
+        vals.extend(receiver.try_iter().map(|msg| msg.val));
+        assert_eq!(vals, [1, 2, 3, 4]);
+    });
+}
+
+#[test]
+fn unlink_delivered_test() {
+    model(|| {
+        let (sender, receiver) = ChannelBuilder::<UsizeTest, usize>::new().build();
+        sender.send(key(1), 1).unwrap();
+        let held = receiver.try_recv().unwrap();
+        sender.send(key(1), 2).unwrap();
+        sender.send(key(3), 3).unwrap();
+        sender.send(key(4), 4).unwrap();
+        let producer = thread::spawn(move || {
+            sender.send(key(5), 5).unwrap();
+            sender.send(key(6), 6).unwrap();
+        });
+
+        // the second block is unlinked behind the blocked msg while the
+        // sender may still walk it.
+        let mut vals: Vec<_> = receiver.try_iter().map(|msg| msg.val).collect();
+        producer.join().unwrap();
+        vals.extend(receiver.try_iter().map(|msg| msg.val));
+        drop(held);
+        vals.extend(receiver.try_iter().map(|msg| msg.val));
+        assert_eq!(vals, [3, 4, 5, 6, 2]);
+    });
+}
+
+#[test]
+fn pinned_send_test() {
+    model_bounded(3, || {
+        let (sender, receiver) = ChannelBuilder::<UsizeTest, usize>::new().build();
+        sender.send(key(1), 1).unwrap();
+        let sync_sender = sender.clone();
+        let producer = thread::spawn(move || sync_sender.send_sync(key(2), 2).unwrap());
+        sender.send(key(3), 3).unwrap();
+
+        // the sync sender waits out of the channel, the first block is not
+        // recycled until it has seen its msg delivered.
+        let mut vals = vec![];
+        while vals.len() < 3 {
+            vals.extend(receiver.try_iter().map(|msg| msg.val));
+            thread::yield_now();
+        }
+        producer.join().unwrap();
+        vals.sort_unstable();
+        assert_eq!(vals, [1, 2, 3]);
+    });
+}
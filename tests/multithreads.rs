@@ -1,7 +1,6 @@
 use mpsc::{async_channel, sync_channel};
 use std::collections::HashSet;
 use std::{
-    collections::HashMap,
     sync::{Arc, Mutex},
     thread,
 };
@@ -30,7 +29,7 @@ impl mpsc::HyperKey for UsizeTest {
 fn naive_async_test() {
     // ugly implementation.
     let chan = Box::leak(Box::new(mpsc::Channel::<SimpleTest, usize>::new()));
-    let sender = async_channel::Sender { chan };
+    let sender = async_channel::Sender::new(chan);
     let receiver = async_channel::Receiver { chan };
 
     let _: Vec<_> = (0..1000)
@@ -58,7 +57,7 @@ fn naive_async_test() {
             let sender = sender.clone();
             thread::spawn(move || {
                 let mut vec = out_of_order.lock().unwrap();
-                sender.send(vec![SimpleTest {}], i);
+                sender.send(vec![SimpleTest {}], i).unwrap();
                 vec.push(i);
             })
         })
@@ -81,14 +80,14 @@ fn async_simple_key_test() {
     // then drops 2
     // receive should be: 2
     let chan = Box::leak(Box::new(mpsc::Channel::<UsizeTest, usize>::new()));
-    let sender = async_channel::Sender { chan };
+    let sender = async_channel::Sender::new(chan);
     let receiver = async_channel::Receiver { chan };
 
     let test_cases = vec![2, 2, 3, 1, 3, 6, 7, 8];
     for elem in test_cases {
         let sender = sender.clone();
-        let _handle = thread::spawn(move || {
-            sender.send(vec![UsizeTest { key: elem }], elem);
+        thread::spawn(move || {
+            sender.send(vec![UsizeTest { key: elem }], elem).unwrap();
         })
         .join()
         .unwrap();
@@ -117,18 +116,26 @@ fn async_simple_key_test() {
 #[test]
 fn fifo_test() {
     let chan = Box::leak(Box::new(mpsc::Channel::<UsizeTest, usize>::new()));
-    let sender = async_channel::Sender { chan };
+    let sender = async_channel::Sender::new(chan);
     let receiver = async_channel::Receiver { chan };
 
     let order = Arc::new(Mutex::new(vec![]));
     let test_cases = vec![2, 2, 3, 1, 3, 6, 7, 8];
-    for elem in test_cases {
-        let sender = sender.clone();
-        let order = order.clone();
-        let _handle = thread::spawn(move || {
-            sender.send(vec![UsizeTest { key: elem }], elem);
-            order.lock().unwrap().push(elem);
-        });
+    let handles: Vec<_> = test_cases
+        .into_iter()
+        .map(|elem| {
+            let sender = sender.clone();
+            let order = order.clone();
+            thread::spawn(move || {
+                sender.send(vec![UsizeTest { key: elem }], elem).unwrap();
+                order.lock().unwrap().push(elem);
+            })
+        })
+        .collect();
+    // async recv gives up once every pending msg collides with the held ones,
+    // the 6 distinct keys must all be pending first.
+    for handle in handles {
+        handle.join().unwrap();
     }
 
     let mut results = vec![];
@@ -137,8 +144,8 @@ fn fifo_test() {
     }
 
     let mut hash_set = HashSet::new();
-    for i in 0..6 {
-        hash_set.insert(results[i].val);
+    for msg in &results {
+        hash_set.insert(msg.val);
     }
     // make sure no collision.
     assert_eq!(hash_set.len(), results.len());
@@ -147,7 +154,7 @@ fn fifo_test() {
 #[test]
 fn naive_sync_test() {
     let chan = Box::leak(Box::new(mpsc::Channel::<SimpleTest, usize>::new()));
-    let sender = sync_channel::Sender { chan };
+    let sender = sync_channel::Sender::new(chan);
     let receiver = sync_channel::Receiver { chan };
     // simple test:
     thread::spawn(move || sender.send(vec![SimpleTest {}], 1));
@@ -157,7 +164,7 @@ fn naive_sync_test() {
 #[test]
 fn sync_simplkey_test() {
     let chan = Box::leak(Box::new(mpsc::Channel::<UsizeTest, usize>::new()));
-    let sender = sync_channel::Sender { chan };
+    let sender = sync_channel::Sender::new(chan);
     let receiver = sync_channel::Receiver { chan };
 
     let order = Arc::new(Mutex::new(vec![]));
@@ -177,9 +184,302 @@ fn sync_simplkey_test() {
     }
 
     let mut hash_set = HashSet::new();
-    for i in 0..6 {
-        hash_set.insert(results[i].val);
+    for msg in &results {
+        hash_set.insert(msg.val);
     }
     // make sure no collision.
     assert_eq!(hash_set.len(), results.len());
 }
+
+#[test]
+fn iter_test() {
+    let chan: &'static _ = Box::leak(Box::new(mpsc::Channel::<UsizeTest, usize>::new()));
+    let sender = sync_channel::Sender::new(chan);
+    let receiver = sync_channel::Receiver { chan };
+
+    let handles: Vec<_> = (0..4)
+        .map(|key| {
+            let sender = sender.clone();
+            thread::spawn(move || {
+                for i in 0..25 {
+                    sender.send(vec![UsizeTest { key }], key * 25 + i).unwrap();
+                }
+            })
+        })
+        .collect();
+    let closer = thread::spawn(move || {
+        for handle in handles {
+            handle.join().unwrap();
+        }
+        chan.close();
+    });
+
+    let mut vals: Vec<_> = receiver.iter().map(|msg| msg.val).collect();
+    closer.join().unwrap();
+    vals.sort_unstable();
+    assert_eq!(vals, (0..100).collect::<Vec<_>>());
+    assert!(receiver.recv().is_err());
+}
+
+#[test]
+fn iter_disconnect_test() {
+    let chan: &'static _ = Box::leak(Box::new(mpsc::Channel::<UsizeTest, usize>::new()));
+    let sender = async_channel::Sender::new(chan);
+    let receiver = async_channel::Receiver { chan };
+
+    let handles: Vec<_> = (0..4)
+        .map(|key| {
+            let sender = sender.clone();
+            thread::spawn(move || {
+                for i in 0..25 {
+                    sender.send(vec![UsizeTest { key }], key * 25 + i).unwrap();
+                }
+            })
+        })
+        .collect();
+    drop(sender);
+
+    // ends once the last sender is dropped, without an explicit close.
+    let mut vals: Vec<_> = receiver.iter().map(|msg| msg.val).collect();
+    for handle in handles {
+        handle.join().unwrap();
+    }
+    vals.sort_unstable();
+    assert_eq!(vals, (0..100).collect::<Vec<_>>());
+    assert!(chan.is_closed());
+}
+
+#[test]
+fn select_test() {
+    let chan1: &'static _ = Box::leak(Box::new(mpsc::Channel::<UsizeTest, usize>::new()));
+    let chan2: &'static _ = Box::leak(Box::new(mpsc::Channel::<SimpleTest, usize>::new()));
+    let sender1 = async_channel::Sender::new(chan1);
+    let sender2 = async_channel::Sender::new(chan2);
+    let receiver1 = async_channel::Receiver { chan: chan1 };
+    let receiver2 = async_channel::Receiver { chan: chan2 };
+
+    let handle = thread::spawn(move || {
+        for i in 0..50 {
+            sender1.send(vec![UsizeTest { key: i }], i).unwrap();
+            sender2.send(vec![SimpleTest {}], i + 50).unwrap();
+        }
+        chan1.close();
+        chan2.close();
+    });
+
+    let mut vals = vec![];
+    while let Ok(val) = mpsc::select! {
+        recv(receiver1) -> msg => msg.val,
+        recv(receiver2) -> msg => msg.val,
+    } {
+        vals.push(val);
+    }
+    handle.join().unwrap();
+    vals.sort_unstable();
+    assert_eq!(vals, (0..100).collect::<Vec<_>>());
+}
+
+#[test]
+fn select_park_test() {
+    let chan1 = mpsc::Channel::<UsizeTest, usize>::new();
+    let chan2 = mpsc::Channel::<SimpleTest, usize>::new();
+    let sender1 = async_channel::Sender::new(&chan1);
+    let sender2 = async_channel::Sender::new(&chan2);
+    let receiver1 = async_channel::Receiver { chan: &chan1 };
+    let receiver2 = async_channel::Receiver { chan: &chan2 };
+
+    let delay = std::time::Duration::from_millis(100);
+    let beginning = std::time::Instant::now();
+    sender1
+        .send_after(vec![UsizeTest { key: 1 }], 1, delay * 2)
+        .unwrap();
+    thread::scope(|s| {
+        s.spawn(|| {
+            thread::sleep(delay);
+            sender2.send(vec![SimpleTest {}], 2).unwrap();
+        });
+
+        // parked until the msg sent meanwhile, then until the scheduled one
+        // matures.
+        for (val, after) in [(2, delay), (1, delay * 2)] {
+            let selected = mpsc::select! {
+                recv(receiver1) -> msg => msg.val,
+                recv(receiver2) -> msg => msg.val,
+                timeout(delay * 10) => 0,
+            };
+            assert_eq!(selected.unwrap(), val);
+            assert!(beginning.elapsed() >= after);
+            assert!(beginning.elapsed() < delay * 10);
+        }
+    });
+}
+
+#[test]
+fn linger_test() {
+    let chan: &'static _ = Box::leak(Box::new(mpsc::Channel::<UsizeTest, usize>::new()));
+    let sender = async_channel::Sender::new(chan);
+    let receiver = async_channel::Receiver { chan };
+
+    let handle = thread::spawn(move || {
+        for i in 0..20 {
+            sender.send(vec![UsizeTest { key: i }], i).unwrap();
+            thread::sleep(std::time::Duration::from_millis(1));
+        }
+        // collides with the first msg, which is still in the batch.
+        sender.send(vec![UsizeTest { key: 0 }], 20).unwrap();
+    });
+
+    let beginning = std::time::Instant::now();
+    let linger = std::time::Duration::from_millis(200);
+    let batch = receiver.recv_batch_timeout(500, linger).unwrap();
+    handle.join().unwrap();
+
+    assert!(beginning.elapsed() >= linger);
+    let vals: Vec<_> = batch.iter().map(|msg| msg.val).collect();
+    assert_eq!(vals, (0..20).collect::<Vec<_>>());
+
+    drop(batch);
+    let batch = receiver.recv_batch_timeout(1, linger).unwrap();
+    assert_eq!(batch[0].val, 20);
+}
+
+#[test]
+fn send_all_test() {
+    let chan: &'static _ = Box::leak(Box::new(mpsc::Channel::<UsizeTest, usize>::new()));
+    let sender = async_channel::Sender::new(chan);
+    let receiver = async_channel::Receiver { chan };
+
+    let handles: Vec<_> = (0..4)
+        .map(|t| {
+            let sender = sender.clone();
+            thread::spawn(move || {
+                for i in 0..25 {
+                    let base = (t * 25 + i) * 10;
+                    sender
+                        .send_all((0..10).map(|j| (vec![UsizeTest { key: base + j }], base + j)))
+                        .unwrap();
+                }
+            })
+        })
+        .collect();
+    for handle in handles {
+        handle.join().unwrap();
+    }
+
+    // every segment is contiguous.
+    let vals: Vec<_> = receiver.try_iter().map(|msg| msg.val).collect();
+    assert_eq!(vals.len(), 1000);
+    for segment in vals.chunks(10) {
+        assert_eq!(segment[0] % 10, 0);
+        assert_eq!(segment, (segment[0]..segment[0] + 10).collect::<Vec<_>>());
+    }
+}
+
+#[test]
+fn watchdog_test() {
+    let chan = mpsc::Channel::<UsizeTest, usize>::new();
+    let sender = async_channel::Sender::new(&chan);
+    let receiver = async_channel::Receiver { chan: &chan };
+
+    let stuck = Arc::new(Mutex::new(vec![]));
+    let watchdog = {
+        let stuck = stuck.clone();
+        chan.watchdog(std::time::Duration::from_millis(20), move |key| {
+            stuck.lock().unwrap().push(key);
+        })
+    };
+
+    sender.send(vec![UsizeTest { key: 1 }], 1).unwrap();
+    sender.send(vec![UsizeTest { key: 2 }], 2).unwrap();
+    let forgotten = receiver.recv().unwrap();
+    drop(receiver.recv().unwrap());
+    thread::sleep(std::time::Duration::from_millis(100));
+    drop(watchdog);
+
+    // reported once, the dropped msg is not.
+    let stuck = stuck.lock().unwrap();
+    assert_eq!(stuck.len(), 1);
+    assert_eq!(stuck[0].key.key, 1);
+    assert!(stuck[0].held_for >= std::time::Duration::from_millis(20));
+    assert!(stuck[0].enqueued_at <= stuck[0].delivered_at);
+    drop(forgotten);
+}
+
+#[test]
+fn scheduled_wakeup_test() {
+    let chan = mpsc::Channel::<UsizeTest, usize>::new();
+    let sender = sync_channel::Sender::new(&chan);
+    let receiver = sync_channel::Receiver { chan: &chan };
+
+    let delay = std::time::Duration::from_millis(200);
+    let beginning = std::time::Instant::now();
+    thread::scope(|s| {
+        let scheduled = sender.clone();
+        let producer = s.spawn(move || scheduled.send_after(vec![UsizeTest { key: 1 }], 1, delay));
+        let immediate = sender.clone();
+        s.spawn(move || {
+            thread::sleep(delay / 4);
+            let _ = immediate.send(vec![UsizeTest { key: 2 }], 2);
+        });
+
+        // the receiver parked until the scheduled msg matures is woken up
+        // by the immediate one.
+        assert_eq!(receiver.recv().unwrap().val, 2);
+        assert!(beginning.elapsed() < delay);
+        assert_eq!(receiver.recv().unwrap().val, 1);
+        assert!(beginning.elapsed() >= delay);
+        assert!(producer.join().unwrap().is_ok());
+    });
+}
+
+/// time the calling thread has spent on a cpu.
+#[cfg(target_os = "linux")]
+fn cpu_time() -> std::time::Duration {
+    let schedstat = std::fs::read_to_string("/proc/thread-self/schedstat").unwrap();
+    let nanos = schedstat.split_whitespace().next().unwrap();
+    std::time::Duration::from_nanos(nanos.parse().unwrap())
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn idle_iter_test() {
+    let chan = mpsc::Channel::<UsizeTest, usize>::new();
+    let sender = sync_channel::Sender::new(&chan);
+    let receiver = sync_channel::Receiver::new(&chan);
+
+    let used = thread::scope(|scope| {
+        let handle = scope.spawn(|| {
+            let beginning = cpu_time();
+            let vals: Vec<_> = receiver.iter().map(|msg| msg.val).collect();
+            assert!(vals.is_empty());
+            cpu_time() - beginning
+        });
+        thread::sleep(std::time::Duration::from_millis(300));
+        drop(sender);
+        handle.join().unwrap()
+    });
+    // the idle receiver is parked, it doesn't spin.
+    assert!(used < std::time::Duration::from_millis(50), "{used:?}");
+}
+
+#[test]
+fn large_msg_test() {
+    // a block of such msgs is larger than the stack of a spawned thread.
+    let chan = Arc::new(mpsc::Channel::<UsizeTest, [u8; 1 << 16]>::new());
+    let producer = {
+        let chan = chan.clone();
+        thread::spawn(move || {
+            let sender = async_channel::Sender::new(&chan);
+            for i in 0..40 {
+                sender
+                    .send(vec![UsizeTest { key: i }], [i as u8; 1 << 16])
+                    .unwrap();
+            }
+        })
+    };
+    producer.join().unwrap();
+
+    let receiver = async_channel::Receiver { chan: &chan };
+    let vals: Vec<_> = receiver.try_iter().map(|msg| msg.val[0]).collect();
+    assert_eq!(vals, (0..40).collect::<Vec<u8>>());
+}
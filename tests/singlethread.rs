@@ -23,16 +23,16 @@ impl mpsc::HyperKey for UsizeTest {
 #[test]
 fn navive_async_test() {
     let chan = mpsc::Channel::<SimpleTest, usize>::new();
-    let sender = async_channel::Sender { chan: &chan };
+    let sender = async_channel::Sender::new(&chan);
     let receiver = async_channel::Receiver { chan: &chan };
     let _ = (0..100000).map(|i| {
-        sender.send(vec![SimpleTest {}], i);
+        sender.send(vec![SimpleTest {}], i).unwrap();
     });
     let _ = (0..100000).map(|i| {
         assert_eq!(i, receiver.recv().unwrap().val);
     });
     let _ = (0..100000).map(|i| {
-        sender.send(vec![SimpleTest {}], i);
+        sender.send(vec![SimpleTest {}], i).unwrap();
         assert_eq!(i, receiver.recv().unwrap().val)
     });
 }
@@ -40,12 +40,12 @@ fn navive_async_test() {
 #[test]
 fn collision_test() {
     let chan = mpsc::Channel::<UsizeTest, usize>::new();
-    let sender = async_channel::Sender { chan: &chan };
+    let sender = async_channel::Sender::new(&chan);
     let receiver = async_channel::Receiver { chan: &chan };
 
-    sender.send(vec![UsizeTest { key: 1 }], 1);
-    sender.send(vec![UsizeTest { key: 1 }], 3);
-    sender.send(vec![UsizeTest { key: 2 }], 2);
+    sender.send(vec![UsizeTest { key: 1 }], 1).unwrap();
+    sender.send(vec![UsizeTest { key: 1 }], 3).unwrap();
+    sender.send(vec![UsizeTest { key: 2 }], 2).unwrap();
 
     let msg1 = receiver.recv().unwrap();
     let msg2 = receiver.recv().unwrap();
@@ -62,7 +62,749 @@ fn collision_test() {
 #[test]
 fn naive_sync_test() {
     let chan = mpsc::Channel::<SimpleTest, usize>::new();
-    let _sender = sync_channel::Sender { chan: &chan };
+    let _sender = sync_channel::Sender::new(&chan);
     // Yeah, it blocks!
     // sender.send(SimpleTest {}, 1).unwrap();
 }
+
+#[test]
+fn try_iter_test() {
+    let chan = mpsc::Channel::<UsizeTest, usize>::new();
+    let sender = async_channel::Sender::new(&chan);
+    let receiver = async_channel::Receiver { chan: &chan };
+
+    sender.send(vec![UsizeTest { key: 1 }], 1).unwrap();
+    sender.send(vec![UsizeTest { key: 1 }], 3).unwrap();
+    sender.send(vec![UsizeTest { key: 2 }], 2).unwrap();
+
+    // key 1 is held by the first msg, so 3 is not deliverable.
+    let msgs: Vec<_> = receiver.try_iter().collect();
+    assert_eq!(
+        msgs.iter().map(|msg| msg.val).collect::<Vec<_>>(),
+        vec![1, 2]
+    );
+    assert_eq!(receiver.try_iter().count(), 0);
+
+    drop(msgs);
+    let vals: Vec<_> = receiver.try_iter().map(|msg| msg.val).collect();
+    assert_eq!(vals, vec![3]);
+}
+
+#[test]
+fn closed_iter_test() {
+    let chan = mpsc::Channel::<UsizeTest, usize>::new();
+    let sender = async_channel::Sender::new(&chan);
+    let receiver = async_channel::Receiver { chan: &chan };
+
+    for i in 0..10 {
+        sender.send(vec![UsizeTest { key: i % 2 }], i).unwrap();
+    }
+    chan.close();
+
+    let vals: Vec<_> = receiver.into_iter().map(|msg| msg.val).collect();
+    assert_eq!(vals, (0..10).collect::<Vec<_>>());
+}
+
+#[test]
+fn send_after_close_test() {
+    let chan = mpsc::Channel::<UsizeTest, usize>::new();
+    let receiver = async_channel::Receiver::new(&chan);
+    drop(async_channel::Sender::new(&chan));
+    assert!(receiver.iter().next().is_none());
+
+    // the channel stays closed, the late senders' msgs are rejected.
+    let sender = async_channel::Sender::new(&chan);
+    assert!(sender.send(vec![UsizeTest { key: 1 }], 1).is_err());
+    let sender = sync_channel::Sender::new(&chan);
+    assert!(sender.send(vec![UsizeTest { key: 2 }], 2).is_err());
+    assert_eq!(chan.pending_len(), 0);
+}
+
+#[test]
+fn select_test() {
+    let chan1 = mpsc::Channel::<UsizeTest, usize>::new();
+    let chan2 = mpsc::Channel::<SimpleTest, String>::new();
+    let sender1 = async_channel::Sender::new(&chan1);
+    let sender2 = async_channel::Sender::new(&chan2);
+    let receiver1 = async_channel::Receiver { chan: &chan1 };
+    let receiver2 = async_channel::Receiver { chan: &chan2 };
+
+    let empty = mpsc::select! {
+        recv(receiver1) -> msg => msg.val,
+        recv(receiver2) -> msg => msg.val.len(),
+        default => 0,
+    };
+    assert_eq!(empty.unwrap(), 0);
+
+    sender2
+        .send(vec![SimpleTest {}], String::from("hello"))
+        .unwrap();
+    let len = mpsc::select! {
+        recv(receiver1) -> msg => msg.val,
+        recv(receiver2) -> msg => msg.val.len(),
+    };
+    assert_eq!(len.unwrap(), 5);
+
+    // the key is still held by the first msg.
+    sender1.send(vec![UsizeTest { key: 1 }], 1).unwrap();
+    sender1.send(vec![UsizeTest { key: 1 }], 2).unwrap();
+    let first = receiver1.recv().unwrap();
+    let timeout = mpsc::select::Select::new()
+        .recv(&receiver1, |msg| msg.val)
+        .timeout(std::time::Duration::from_millis(10), || 0)
+        .wait();
+    assert_eq!(timeout.unwrap(), 0);
+
+    drop(first);
+    chan2.close();
+    let second = mpsc::select! {
+        recv(receiver2) -> _msg => 0,
+        recv(receiver1) -> msg => msg.val,
+    };
+    assert_eq!(second.unwrap(), 2);
+
+    chan1.close();
+    let closed = mpsc::select! {
+        recv(receiver1) -> msg => msg.val,
+        recv(receiver2) -> msg => msg.val.len(),
+    };
+    assert!(closed.is_err());
+}
+
+#[test]
+fn select_fairness_test() {
+    let chan1 = mpsc::Channel::<UsizeTest, usize>::new();
+    let chan2 = mpsc::Channel::<UsizeTest, usize>::new();
+    let sender1 = async_channel::Sender::new(&chan1);
+    let sender2 = async_channel::Sender::new(&chan2);
+    let receiver1 = async_channel::Receiver { chan: &chan1 };
+    let receiver2 = async_channel::Receiver { chan: &chan2 };
+
+    for i in 0..10 {
+        sender1.send(vec![UsizeTest { key: i }], 1).unwrap();
+        sender2.send(vec![UsizeTest { key: i }], 2).unwrap();
+    }
+    // the busy first channel doesn't starve the second one.
+    let vals: Vec<_> = (0..10)
+        .map(|_| {
+            mpsc::select! {
+                recv(receiver1) -> msg => msg.val,
+                recv(receiver2) -> msg => msg.val,
+            }
+            .unwrap()
+        })
+        .collect();
+    assert_eq!(vals.iter().filter(|&&val| val == 1).count(), 5);
+    assert_eq!(vals.iter().filter(|&&val| val == 2).count(), 5);
+}
+
+#[test]
+fn priority_test() {
+    let chan = mpsc::Channel::<UsizeTest, usize>::new();
+    let sender = async_channel::Sender::new(&chan);
+    let receiver = async_channel::Receiver { chan: &chan };
+
+    sender.send(vec![UsizeTest { key: 1 }], 1).unwrap();
+    sender.send(vec![UsizeTest { key: 2 }], 2).unwrap();
+    sender
+        .send_with_priority(vec![UsizeTest { key: 2 }], 3, 10)
+        .unwrap();
+    sender
+        .send_with_priority(vec![UsizeTest { key: 3 }], 4, 10)
+        .unwrap();
+    sender
+        .send_with_priority(vec![UsizeTest { key: 4 }], 5, 20)
+        .unwrap();
+
+    // 3 can't overtake 2, which shares its key.
+    let vals: Vec<_> = receiver.try_iter().map(|msg| msg.val).collect();
+    assert_eq!(vals, vec![5, 4, 1, 2, 3]);
+}
+
+#[test]
+// miri runs too slowly for the timings to hold.
+#[cfg_attr(miri, ignore)]
+fn scheduled_test() {
+    let chan = mpsc::Channel::<UsizeTest, usize>::new();
+    let sender = async_channel::Sender::new(&chan);
+    let receiver = async_channel::Receiver { chan: &chan };
+
+    let delay = std::time::Duration::from_millis(50);
+    let beginning = std::time::Instant::now();
+    sender
+        .send_after(vec![UsizeTest { key: 1 }], 1, delay)
+        .unwrap();
+    sender
+        .send_at(vec![UsizeTest { key: 1 }], 2, beginning + delay * 2)
+        .unwrap();
+    // the scheduled msgs don't block the later one.
+    sender.send(vec![UsizeTest { key: 1 }], 3).unwrap();
+
+    assert_eq!(receiver.recv().unwrap().val, 3);
+    assert_eq!(receiver.try_iter().count(), 0);
+
+    // recv parks until the first scheduled msg matures.
+    assert_eq!(receiver.recv().unwrap().val, 1);
+    assert!(beginning.elapsed() >= delay);
+    assert_eq!(receiver.recv().unwrap().val, 2);
+    assert!(beginning.elapsed() >= delay * 2);
+}
+
+#[test]
+fn coalescing_test() {
+    let chan = mpsc::Channel::<UsizeTest, usize>::coalescing();
+    let sender = async_channel::Sender::new(&chan);
+    let receiver = async_channel::Receiver { chan: &chan };
+
+    sender.send(vec![UsizeTest { key: 1 }], 1).unwrap();
+    let held = receiver.recv().unwrap();
+
+    // the delivered msg is never replaced, the pending ones are.
+    for i in 2..10 {
+        sender.send(vec![UsizeTest { key: 1 }], i).unwrap();
+        sender.send(vec![UsizeTest { key: 2 }], i * 10).unwrap();
+    }
+    sender
+        .send(vec![UsizeTest { key: 1 }, UsizeTest { key: 2 }], 100)
+        .unwrap();
+    sender.send(vec![UsizeTest { key: 2 }], 200).unwrap();
+
+    let vals: Vec<_> = receiver.try_iter().map(|msg| msg.val).collect();
+    assert_eq!(vals, vec![90]);
+    assert_eq!(held.val, 1);
+
+    drop(held);
+    let vals: Vec<_> = receiver.try_iter().map(|msg| msg.val).collect();
+    assert_eq!(vals, vec![9, 100, 200]);
+}
+
+#[test]
+fn folding_test() {
+    let chan = mpsc::Channel::<UsizeTest, usize>::folding(|sum, val| *sum += val);
+    let sender = async_channel::Sender::new(&chan);
+    let receiver = async_channel::Receiver { chan: &chan };
+
+    for i in 1..=10 {
+        sender.send(vec![UsizeTest { key: i % 2 }], i).unwrap();
+    }
+
+    let vals: Vec<_> = receiver.try_iter().map(|msg| msg.val).collect();
+    assert_eq!(vals, vec![25, 30]);
+}
+
+#[test]
+fn coalescing_opts_test() {
+    let chan = mpsc::Channel::<UsizeTest, usize>::coalescing();
+    let sender = async_channel::Sender::new(&chan);
+    let receiver = async_channel::Receiver { chan: &chan };
+
+    // a scheduled msg is not folded into a visible one, it'd show up early.
+    let hour = std::time::Duration::from_secs(3600);
+    sender.send(vec![UsizeTest { key: 1 }], 1).unwrap();
+    sender
+        .send_after(vec![UsizeTest { key: 1 }], 2, hour)
+        .unwrap();
+    // nor is an urgent msg folded into a less urgent one.
+    sender.send(vec![UsizeTest { key: 2 }], 10).unwrap();
+    sender
+        .send_with_priority(vec![UsizeTest { key: 2 }], 20, 1)
+        .unwrap();
+    // the other way around, a visible msg isn't delayed by a scheduled one.
+    sender
+        .send_after(vec![UsizeTest { key: 3 }], 100, hour)
+        .unwrap();
+    sender.send(vec![UsizeTest { key: 3 }], 200).unwrap();
+    assert_eq!(chan.stats().coalesced, 0);
+    // msgs maturing at once are folded.
+    let at = std::time::Instant::now() + hour;
+    sender.send_at(vec![UsizeTest { key: 4 }], 300, at).unwrap();
+    sender.send_at(vec![UsizeTest { key: 4 }], 400, at).unwrap();
+    assert_eq!(chan.stats().coalesced, 1);
+
+    let vals: Vec<_> = receiver.try_iter().map(|msg| msg.val).collect();
+    assert_eq!(vals, vec![1, 10, 20, 200]);
+    assert_eq!(chan.pending_len(), 3);
+}
+
+#[test]
+fn batch_test() {
+    let chan = mpsc::Channel::<UsizeTest, usize>::new();
+    let sender = async_channel::Sender::new(&chan);
+    let receiver = async_channel::Receiver { chan: &chan };
+
+    sender.send(vec![UsizeTest { key: 1 }], 1).unwrap();
+    let held = receiver.recv().unwrap();
+
+    sender.send(vec![UsizeTest { key: 1 }], 2).unwrap();
+    sender.send(vec![UsizeTest { key: 2 }], 3).unwrap();
+    sender
+        .send(vec![UsizeTest { key: 2 }, UsizeTest { key: 3 }], 4)
+        .unwrap();
+    sender.send(vec![UsizeTest { key: 4 }], 5).unwrap();
+    sender.send(vec![UsizeTest { key: 5 }], 6).unwrap();
+    sender.send(vec![UsizeTest { key: 6 }], 7).unwrap();
+
+    let batch = receiver.recv_batch(3).unwrap();
+    assert_eq!(
+        batch.iter().map(|msg| msg.val).collect::<Vec<_>>(),
+        vec![3, 5, 6]
+    );
+
+    // every key of the batch is active.
+    let vals: Vec<_> = receiver.try_iter().map(|msg| msg.val).collect();
+    assert_eq!(vals, vec![7]);
+
+    drop(held);
+    drop(batch);
+    let batch = receiver.recv_batch(10).unwrap();
+    assert_eq!(
+        batch.iter().map(|msg| msg.val).collect::<Vec<_>>(),
+        vec![2, 4]
+    );
+
+    chan.close();
+    assert!(receiver.recv_batch(10).is_err());
+}
+
+#[test]
+fn group_test() {
+    let chan = mpsc::Channel::<UsizeTest, usize>::new();
+    let sender = async_channel::Sender::new(&chan);
+    let receiver = async_channel::Receiver { chan: &chan };
+
+    sender.send(vec![UsizeTest { key: 1 }], 1).unwrap();
+    sender.send(vec![UsizeTest { key: 2 }], 2).unwrap();
+    sender.send(vec![UsizeTest { key: 1 }], 3).unwrap();
+    sender
+        .send(vec![UsizeTest { key: 1 }, UsizeTest { key: 2 }], 4)
+        .unwrap();
+    sender.send(vec![UsizeTest { key: 1 }], 5).unwrap();
+    sender.send(vec![UsizeTest { key: 3 }], 6).unwrap();
+
+    // 5 can't overtake 4, which shares key 1.
+    let group = receiver.recv_group().unwrap();
+    assert_eq!(group.keys[0].key, 1);
+    assert_eq!(group.vals, vec![1, 3]);
+
+    let vals: Vec<_> = receiver.try_iter().map(|msg| msg.val).collect();
+    assert_eq!(vals, vec![2, 6]);
+
+    drop(group);
+    let group = receiver.recv_group().unwrap();
+    assert_eq!(group.vals, vec![4]);
+    drop(group);
+    assert_eq!(receiver.recv_group().unwrap().vals, vec![5]);
+}
+
+#[test]
+fn stats_test() {
+    let chan = mpsc::Channel::<UsizeTest, usize>::coalescing();
+    let sender = async_channel::Sender::new(&chan);
+    let receiver = async_channel::Receiver { chan: &chan };
+
+    sender.send(vec![UsizeTest { key: 1 }], 1).unwrap();
+    sender
+        .send(vec![UsizeTest { key: 2 }, UsizeTest { key: 3 }], 2)
+        .unwrap();
+    let msg = receiver.recv().unwrap();
+    sender.send(vec![UsizeTest { key: 1 }], 3).unwrap();
+    sender.send(vec![UsizeTest { key: 1 }], 4).unwrap();
+    sender.send(vec![UsizeTest { key: 4 }], 5).unwrap();
+
+    let stats = chan.stats();
+    assert_eq!(stats.sent, 5);
+    assert_eq!(stats.coalesced, 1);
+    assert_eq!(stats.delivered, 1);
+    assert_eq!(stats.released, 0);
+    assert_eq!(stats.pending, 3);
+    // no receive has seen the msg of key 1 yet.
+    assert_eq!(stats.blocked, 0);
+    assert_eq!(stats.active_keys, 1);
+    assert_eq!(stats.awaiting_reclamation, 1);
+
+    let batch = receiver.recv_batch(10).unwrap();
+    let stats = chan.stats();
+    assert_eq!(stats.delivered, 3);
+    assert_eq!(stats.pending, 1);
+    assert_eq!(stats.blocked, 1);
+    assert_eq!(stats.active_keys, 4);
+
+    drop(msg);
+    let last = receiver.recv().unwrap();
+    let stats = chan.stats();
+    assert_eq!(stats.released, 1);
+    assert_eq!(stats.pending, 0);
+    assert_eq!(stats.blocked, 0);
+    assert_eq!(stats.active_keys, 4);
+
+    drop(batch);
+    drop(last);
+    assert_eq!(chan.stats().released, 4);
+}
+
+#[test]
+fn hot_keys_test() {
+    let chan = mpsc::Channel::<UsizeTest, usize>::new().with_hot_keys();
+    let sender = async_channel::Sender::new(&chan);
+    let receiver = async_channel::Receiver { chan: &chan };
+
+    sender.send(vec![UsizeTest { key: 1 }], 1).unwrap();
+    sender.send(vec![UsizeTest { key: 2 }], 2).unwrap();
+    let held = receiver.recv_batch(2).unwrap();
+    for i in 0..3 {
+        sender.send(vec![UsizeTest { key: 1 }], i).unwrap();
+    }
+    sender.send(vec![UsizeTest { key: 2 }], 3).unwrap();
+
+    // each pending msg of the held keys counts once, however many scans skip it.
+    assert!(receiver.recv().is_err());
+    assert!(receiver.recv().is_err());
+
+    let hot_keys = chan.hot_keys(1);
+    assert_eq!(hot_keys.len(), 1);
+    assert_eq!(hot_keys[0].key.key, 1);
+    assert_eq!(hot_keys[0].skips, 3);
+    assert_eq!(hot_keys[0].queue_depth, 3);
+    assert!(hot_keys[0].held);
+
+    drop(held);
+    let hot_keys = chan.hot_keys(2);
+    assert_eq!(hot_keys[1].key.key, 2);
+    assert_eq!(hot_keys[1].skips, 1);
+    assert_eq!(hot_keys[1].queue_depth, 1);
+    assert!(!hot_keys[1].held);
+}
+
+#[test]
+// miri runs too slowly for the timings to hold.
+#[cfg_attr(miri, ignore)]
+fn latencies_test() {
+    let chan = mpsc::Channel::<UsizeTest, usize>::new();
+    let sender = async_channel::Sender::new(&chan);
+    let receiver = async_channel::Receiver { chan: &chan };
+    let pause = std::time::Duration::from_millis(20);
+
+    sender.send(vec![UsizeTest { key: 1 }], 1).unwrap();
+    sender.send(vec![UsizeTest { key: 1 }], 2).unwrap();
+    let msg = receiver.recv().unwrap();
+    // the second msg is seen blocked.
+    assert!(receiver.recv().is_err());
+    std::thread::sleep(pause);
+    drop(msg);
+    std::thread::sleep(pause);
+    drop(receiver.recv().unwrap());
+
+    let latencies = chan.latencies();
+    assert_eq!(latencies.queueing.count(), 2);
+    assert_eq!(latencies.blocked.count(), 2);
+    assert_eq!(latencies.hold.count(), 2);
+    assert!(latencies.blocked.max() >= 2 * pause);
+    assert!(latencies.hold.max() >= pause);
+    assert!(latencies.queueing.max() < pause);
+    assert!(latencies.blocked.percentile(50) < pause);
+    assert!(latencies.blocked.percentile(100) >= 2 * pause);
+    assert_eq!(
+        latencies
+            .hold
+            .buckets()
+            .map(|(_, count)| count)
+            .sum::<usize>(),
+        2
+    );
+}
+
+#[test]
+fn introspection_test() {
+    let chan = mpsc::Channel::<UsizeTest, usize>::new();
+    let sender = async_channel::Sender::new(&chan);
+    let receiver = async_channel::Receiver { chan: &chan };
+
+    sender
+        .send(vec![UsizeTest { key: 1 }, UsizeTest { key: 2 }], 1)
+        .unwrap();
+    sender.send(vec![UsizeTest { key: 1 }], 2).unwrap();
+    sender.send(vec![UsizeTest { key: 3 }], 3).unwrap();
+    assert_eq!(chan.pending_len(), 3);
+    assert!(chan.active_keys().is_empty());
+
+    let msg = receiver.recv().unwrap();
+    let active: Vec<_> = chan.active_keys().iter().map(|key| key.key).collect();
+    assert_eq!(active, vec![1, 2]);
+    assert!(chan.is_key_active(&UsizeTest { key: 2 }));
+    assert!(!chan.is_key_active(&UsizeTest { key: 3 }));
+    assert_eq!(chan.pending_len(), 2);
+    let pending: Vec<Vec<_>> = chan
+        .pending_keys()
+        .map(|keys| keys.iter().map(|key| key.key).collect())
+        .collect();
+    assert_eq!(pending, vec![vec![1], vec![3]]);
+
+    drop(msg);
+    assert!(!chan.is_key_active(&UsizeTest { key: 1 }));
+}
+
+#[test]
+fn blocked_msgs_test() {
+    let chan = mpsc::Channel::<UsizeTest, usize>::new();
+    let sender = async_channel::Sender::new(&chan);
+    let receiver = async_channel::Receiver { chan: &chan };
+
+    sender.send(vec![UsizeTest { key: 1 }], 1).unwrap();
+    sender
+        .send(vec![UsizeTest { key: 1 }, UsizeTest { key: 2 }], 2)
+        .unwrap();
+    sender.send(vec![UsizeTest { key: 2 }], 3).unwrap();
+    sender.send(vec![UsizeTest { key: 3 }], 4).unwrap();
+    let msg = receiver.recv().unwrap();
+
+    let blocked = chan.blocked_msgs();
+    assert_eq!(blocked.len(), 2);
+    assert_eq!(blocked[0].position, 0);
+    assert_eq!(blocked[0].blockers.len(), 1);
+    assert_eq!(blocked[0].blockers[0].key.key, 1);
+    assert!(!blocked[0].behind_earlier);
+    assert_eq!(blocked[1].position, 1);
+    assert!(blocked[1].blockers.is_empty());
+    assert!(blocked[1].behind_earlier);
+
+    assert_eq!(chan.blockers(&[UsizeTest { key: 1 }]).len(), 1);
+    assert!(chan.blockers(&[UsizeTest { key: 3 }]).is_empty());
+    drop(msg);
+    // only held back by the msg which is now deliverable.
+    let blocked = chan.blocked_msgs();
+    assert_eq!(blocked.len(), 1);
+    assert!(blocked[0].blockers.is_empty());
+    assert!(blocked[0].behind_earlier);
+}
+
+#[derive(Default)]
+struct Audit {
+    log: std::sync::Mutex<Vec<String>>,
+}
+
+fn keys_of(keys: &[UsizeTest]) -> Vec<usize> {
+    keys.iter().map(|key| key.key).collect()
+}
+
+impl mpsc::observer::ChannelObserver<UsizeTest> for std::sync::Arc<Audit> {
+    fn on_enqueue(&self, keys: &[UsizeTest]) {
+        let entry = format!("enqueue {:?}", keys_of(keys));
+        self.log.lock().unwrap().push(entry);
+    }
+
+    fn on_skip(&self, keys: &[UsizeTest], blocking_keys: &[UsizeTest]) {
+        let entry = format!("skip {:?} by {:?}", keys_of(keys), keys_of(blocking_keys));
+        self.log.lock().unwrap().push(entry);
+    }
+
+    fn on_deliver(&self, keys: &[UsizeTest]) {
+        let entry = format!("deliver {:?}", keys_of(keys));
+        self.log.lock().unwrap().push(entry);
+    }
+
+    fn on_release(&self, keys: &[UsizeTest]) {
+        let entry = format!("release {:?}", keys_of(keys));
+        self.log.lock().unwrap().push(entry);
+    }
+}
+
+#[test]
+fn observer_test() {
+    let audit = std::sync::Arc::new(Audit::default());
+    let chan = mpsc::Channel::<UsizeTest, usize>::new().with_observer(audit.clone());
+    let sender = async_channel::Sender::new(&chan);
+    let receiver = async_channel::Receiver { chan: &chan };
+
+    sender.send(vec![UsizeTest { key: 1 }], 1).unwrap();
+    sender
+        .send(vec![UsizeTest { key: 1 }, UsizeTest { key: 2 }], 2)
+        .unwrap();
+    let msg = receiver.recv().unwrap();
+    // skipped twice, reported once.
+    assert!(receiver.recv().is_err());
+    assert!(receiver.recv().is_err());
+    drop(msg);
+    drop(receiver.recv().unwrap());
+
+    assert_eq!(
+        *audit.log.lock().unwrap(),
+        vec![
+            "enqueue [1]",
+            "enqueue [1, 2]",
+            "deliver [1]",
+            "skip [1, 2] by [1]",
+            "release [1]",
+            "deliver [1, 2]",
+            "release [1, 2]",
+        ]
+    );
+}
+
+#[test]
+fn dump_test() {
+    let chan = mpsc::Channel::<UsizeTest, usize>::new();
+    let sender = async_channel::Sender::new(&chan);
+    let receiver = async_channel::Receiver { chan: &chan };
+    assert!(chan.dump().contains("head -> #1\ntail -> #1\n"));
+
+    sender.send(vec![UsizeTest { key: 1 }], 1).unwrap();
+    sender
+        .send_with_priority(vec![UsizeTest { key: 2 }], 2, 3)
+        .unwrap();
+    let msg = receiver.recv().unwrap();
+
+    assert_eq!(
+        chan.dump(),
+        "Channel (open)\n\
+         head -> #1\n  \
+         #1 pending [UsizeTest { key: 1 }]\n  \
+         #2 delivered p3\n\
+         tail -> #3\n\
+         active keys: [UsizeTest { key: 2 }]\n"
+    );
+    let mermaid = chan.to_mermaid();
+    assert!(mermaid.starts_with("graph TD;\n"));
+    assert!(mermaid.contains("    node1-->node2;\n"));
+    assert!(mermaid.contains("    head-->node1;\n"));
+    assert!(mermaid.contains("    tail-->free;\n"));
+    assert!(mermaid.contains("key1[\"UsizeTest { key: 2 }\"];"));
+    let dot = chan.to_dot();
+    assert!(dot.starts_with("digraph channel {"));
+    assert!(dot.contains("node2 [label=\"#2 delivered p3\", style=dashed];"));
+    assert_eq!(
+        format!("{chan:?}"),
+        "Channel { name: None, closed: false, pending: 1, active_keys: [UsizeTest { key: 2 }], .. }"
+    );
+    drop(msg);
+}
+
+#[test]
+fn drop_pending_test() {
+    let val = std::sync::Arc::new(());
+    let chan = mpsc::Channel::<UsizeTest, std::sync::Arc<()>>::new();
+    let sender = async_channel::Sender::new(&chan);
+    let receiver = async_channel::Receiver { chan: &chan };
+
+    for key in 0..3 {
+        sender.send(vec![UsizeTest { key }], val.clone()).unwrap();
+    }
+    let msg = receiver.recv().unwrap();
+    drop(sender);
+    drop(chan);
+    // the undelivered msgs are dropped with the channel, the delivered one lives on.
+    assert_eq!(std::sync::Arc::strong_count(&val), 2);
+    drop(msg);
+    assert_eq!(std::sync::Arc::strong_count(&val), 1);
+}
+
+#[test]
+fn blocks_test() {
+    let chan = mpsc::Channel::<UsizeTest, usize>::new();
+    let sender = async_channel::Sender::new(&chan);
+    let receiver = async_channel::Receiver { chan: &chan };
+
+    // more msgs than a block holds, the segment spans two blocks.
+    sender.send(vec![UsizeTest { key: 0 }], 0).unwrap();
+    sender
+        .send_all((1..100).map(|key| (vec![UsizeTest { key }], key)))
+        .unwrap();
+    let vals: Vec<_> = receiver.try_iter().map(|msg| msg.val).collect();
+    assert_eq!(vals, (0..100).collect::<Vec<_>>());
+
+    // the scans start after the delivered msgs.
+    sender.send(vec![UsizeTest { key: 100 }], 100).unwrap();
+    let dump = chan.dump();
+    assert!(dump.contains("head -> #101\n"));
+    assert!(dump.contains("  #101 pending [UsizeTest { key: 100 }]\ntail -> #102\n"));
+
+    // the blocks behind the head are unlinked, the dump starts at the head's.
+    assert!(!dump.contains("#1 delivered"));
+    assert!(dump.contains("head -> #101\n  #97 delivered\n"));
+}
+
+#[test]
+fn with_capacity_test() {
+    let chan = mpsc::Channel::<UsizeTest, usize>::with_capacity(100);
+    let sender = async_channel::Sender::new(&chan);
+    let receiver = async_channel::Receiver { chan: &chan };
+
+    // the recycled blocks take the msgs of the later rounds.
+    for round in 0..10 {
+        sender
+            .send_all((0..100).map(|key| (vec![UsizeTest { key }], round * 100 + key)))
+            .unwrap();
+        let vals: Vec<_> = receiver.try_iter().map(|msg| msg.val).collect();
+        assert_eq!(vals, (round * 100..round * 100 + 100).collect::<Vec<_>>());
+    }
+    assert_eq!(chan.pending_len(), 0);
+}
+
+#[test]
+fn drained_recv_test() {
+    let chan = mpsc::Channel::<UsizeTest, usize>::new();
+    let sender = async_channel::Sender::new(&chan);
+    let receiver = async_channel::Receiver::new(&chan);
+
+    sender.send(vec![UsizeTest { key: 1 }], 1).unwrap();
+    assert_eq!(receiver.recv().unwrap().val, 1);
+    // the senders are alive, but the channel is drained.
+    assert!(receiver.recv().is_err());
+    assert!(receiver.recv_batch(2).is_err());
+}
+
+#[test]
+fn long_delay_test() {
+    let chan = mpsc::Channel::<UsizeTest, usize>::new();
+    let sender = async_channel::Sender::new(&chan);
+    let receiver = async_channel::Receiver::new(&chan);
+
+    let hour = std::time::Duration::from_secs(3600);
+    sender
+        .send_after(vec![UsizeTest { key: 0 }], 0, hour)
+        .unwrap();
+    let mut allocated = 0;
+    for round in 0..100 {
+        sender
+            .send_all((1..=40).map(|key| (vec![UsizeTest { key }], key)))
+            .unwrap();
+        assert_eq!(receiver.try_iter().count(), 40);
+        if round == 10 {
+            allocated = chan.stats().allocated_blocks;
+        }
+    }
+    // the scheduled msg doesn't hold the head back, the blocks are reused.
+    assert_eq!(chan.stats().allocated_blocks, allocated);
+    assert_eq!(chan.pending_len(), 1);
+    assert!(chan
+        .dump()
+        .contains("scheduled: [[UsizeTest { key: 0 }]]\n"));
+}
+
+#[test]
+fn held_key_test() {
+    let chan = mpsc::Channel::<UsizeTest, usize>::new();
+    let sender = async_channel::Sender::new(&chan);
+    let receiver = async_channel::Receiver::new(&chan);
+
+    sender.send(vec![UsizeTest { key: 0 }], 0).unwrap();
+    let held = receiver.recv().unwrap();
+    sender.send(vec![UsizeTest { key: 0 }], 1).unwrap();
+    let mut allocated = 0;
+    for round in 0..100 {
+        sender
+            .send_all((1..=40).map(|key| (vec![UsizeTest { key }], key)))
+            .unwrap();
+        assert_eq!(receiver.try_iter().count(), 40);
+        if round == 10 {
+            allocated = chan.stats().allocated_blocks;
+        }
+    }
+    // the blocked msg holds its own block only, the blocks delivered after
+    // it are reused.
+    assert_eq!(chan.stats().allocated_blocks, allocated);
+    assert!(chan.dump().lines().count() < 3 * 32);
+
+    drop(held);
+    assert_eq!(receiver.recv().unwrap().val, 1);
+    assert!(chan.dump().contains("head -> #4003\n"));
+}
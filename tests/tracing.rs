@@ -0,0 +1,169 @@
+#![cfg(feature = "tracing")]
+
+use mpsc::async_channel;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::{Event, Metadata, Subscriber};
+use tracing_core::span::Current;
+
+#[derive(Debug, Clone)]
+struct UsizeTest {
+    key: usize,
+}
+
+impl mpsc::HyperKey for UsizeTest {
+    fn collision_detect(&self, other: &Self) -> bool {
+        self.key == other.key
+    }
+}
+
+type SpanInfo = (Id, &'static Metadata<'static>, Option<Id>);
+
+/// records the event messages and the follows-from links, single threaded.
+#[derive(Clone, Default)]
+struct Recorder {
+    next_id: Arc<AtomicU64>,
+    /// id, metadata and parent of every span.
+    spans: Arc<Mutex<Vec<SpanInfo>>>,
+    stack: Arc<Mutex<Vec<Id>>>,
+    events: Arc<Mutex<Vec<String>>>,
+    follows: Arc<Mutex<Vec<(Id, Id)>>>,
+}
+
+struct Message(String);
+
+impl Visit for Message {
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        if field.name() == "message" {
+            self.0 = format!("{value:?}");
+        }
+    }
+}
+
+impl Subscriber for Recorder {
+    fn enabled(&self, _: &Metadata<'_>) -> bool {
+        true
+    }
+
+    fn new_span(&self, span: &Attributes<'_>) -> Id {
+        let id = Id::from_u64(self.next_id.fetch_add(1, Ordering::SeqCst) + 1);
+        let parent = if span.is_contextual() {
+            self.stack.lock().unwrap().last().cloned()
+        } else {
+            span.parent().cloned()
+        };
+        self.spans
+            .lock()
+            .unwrap()
+            .push((id.clone(), span.metadata(), parent));
+        id
+    }
+
+    fn record(&self, _: &Id, _: &Record<'_>) {}
+
+    fn record_follows_from(&self, span: &Id, follows: &Id) {
+        self.follows
+            .lock()
+            .unwrap()
+            .push((span.clone(), follows.clone()));
+    }
+
+    fn event(&self, event: &Event<'_>) {
+        let mut message = Message(String::new());
+        event.record(&mut message);
+        self.events.lock().unwrap().push(message.0);
+    }
+
+    fn enter(&self, span: &Id) {
+        self.stack.lock().unwrap().push(span.clone());
+    }
+
+    fn exit(&self, _: &Id) {
+        self.stack.lock().unwrap().pop();
+    }
+
+    fn current_span(&self) -> Current {
+        let stack = self.stack.lock().unwrap();
+        let Some(id) = stack.last() else {
+            return Current::none();
+        };
+        let spans = self.spans.lock().unwrap();
+        let (_, metadata, _) = spans.iter().find(|(elem, ..)| elem == id).unwrap();
+        Current::new(id.clone(), metadata)
+    }
+}
+
+#[test]
+fn span_test() {
+    let chan = mpsc::Channel::<UsizeTest, usize>::new();
+    let sender = async_channel::Sender::new(&chan);
+    let receiver = async_channel::Receiver { chan: &chan };
+    let recorder = Recorder::default();
+
+    tracing::subscriber::with_default(recorder.clone(), || {
+        let producer = tracing::info_span!("producer");
+        producer.in_scope(|| {
+            sender.send(vec![UsizeTest { key: 1 }], 1).unwrap();
+            sender.send(vec![UsizeTest { key: 1 }], 2).unwrap();
+        });
+
+        let consumer = tracing::info_span!("consumer");
+        let _guard = consumer.enter();
+        let msg = receiver.recv().unwrap();
+        let spans = recorder.spans.lock().unwrap();
+        let (_, metadata, parent) = spans
+            .iter()
+            .find(|(id, ..)| Some(id) == msg.span().id().as_ref())
+            .unwrap();
+        assert_eq!(metadata.name(), "send");
+        assert_eq!(*parent, producer.id());
+        drop(spans);
+        assert!(receiver.recv().is_err());
+        drop(msg);
+        drop(receiver.recv().unwrap());
+    });
+
+    let events = recorder.events.lock().unwrap();
+    for name in ["send", "deliver", "skip", "release"] {
+        assert!(events.iter().any(|event| event == name), "no {name} event");
+    }
+    // the recv spans follow from the send spans.
+    let spans = recorder.spans.lock().unwrap();
+    for (_, follows) in recorder.follows.lock().unwrap().iter() {
+        let (_, metadata, _) = spans.iter().find(|(id, ..)| id == follows).unwrap();
+        assert_eq!(metadata.name(), "send");
+    }
+    assert!(!recorder.follows.lock().unwrap().is_empty());
+}
+
+#[test]
+fn send_after_span_test() {
+    let chan = mpsc::Channel::<UsizeTest, usize>::new();
+    let sender = async_channel::Sender::new(&chan);
+    let receiver = async_channel::Receiver { chan: &chan };
+    let recorder = Recorder::default();
+
+    tracing::subscriber::with_default(recorder.clone(), || {
+        let producer = tracing::info_span!("producer");
+        producer.in_scope(|| {
+            sender
+                .send_after(vec![UsizeTest { key: 1 }], 1, Duration::from_millis(20))
+                .unwrap();
+        });
+
+        // waits until the msg matures, and promotes it in its own span.
+        let consumer = tracing::info_span!("consumer");
+        let _guard = consumer.enter();
+        let msg = receiver.recv().unwrap();
+        let spans = recorder.spans.lock().unwrap();
+        let (_, metadata, parent) = spans
+            .iter()
+            .find(|(id, ..)| Some(id) == msg.span().id().as_ref())
+            .unwrap();
+        assert_eq!(metadata.name(), "send");
+        assert_eq!(*parent, producer.id());
+    });
+}